@@ -0,0 +1,242 @@
+//! Difficulty metrics of a laid-out board, as competitive players measure it.
+//!
+//! Every safe cell belongs to exactly one region. An *opening* is a connected
+//! patch of blank cells plus the numbers bordering it: one click clears the
+//! lot. A number bordering two openings is assigned to the first one found in
+//! row-major order, since clearing either uncovers it. The numbers left over
+//! touch no blank and must each be clicked; connected groups of them form
+//! *islands*. 3BV, the minimum number of left clicks that clears the board, is
+//! then one per opening plus one per island cell.
+//!
+//! Unlike [`solver`](crate::solver), this reads the whole layout, covered
+//! cells included: it describes the board, not the player's view of it.
+//! [`analyze`] takes any laid board, such as one drawn from a share code or
+//! read back from a save; [`analyze_ended`] is for a game still in someone's
+//! hands, and answers once it is over.
+
+use std::collections::VecDeque;
+
+use crate::game::{Board, Cell, GameState};
+
+/// Which region a cell falls in. Indices count from 0 in row-major order of
+/// each region's first cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    /// Not a cell at all, but a hole in the board's mask.
+    Gap,
+    Mine,
+    Opening(usize),
+    Island(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    width: usize,
+    regions: Vec<Region>,
+    openings: usize,
+    islands: usize,
+    three_bv: usize,
+}
+
+impl Analysis {
+    pub fn region(&self, row: usize, col: usize) -> Region {
+        self.regions[row * self.width + col]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Region]> {
+        self.regions.chunks(self.width)
+    }
+
+    pub fn openings(&self) -> usize {
+        self.openings
+    }
+
+    pub fn islands(&self) -> usize {
+        self.islands
+    }
+
+    /// Bechtel's Board Benchmark Value: the fewest left clicks that clear the
+    /// board, with no flags and no chording.
+    pub fn three_bv(&self) -> usize {
+        self.three_bv
+    }
+}
+
+/// [`analyze`] for a game being played: `None` until it is over, while the
+/// regions would still tell the player where the mines are.
+pub fn analyze_ended(state: &GameState) -> Option<Analysis> {
+    match state.is_over() {
+        true => analyze(&state.board),
+        false => None,
+    }
+}
+
+/// The region decomposition and its counts, or `None` before the first
+/// reveal, when there are no mines to measure.
+pub fn analyze(board: &Board) -> Option<Analysis> {
+    if !board.is_seeded() {
+        return None;
+    }
+    let config = board.config();
+    let width = config.width;
+    let content = |(row, col): (usize, usize)| board.cell(row, col).content;
+    let mut regions: Vec<Option<Region>> = (0..config.rows())
+        .flat_map(|row| (0..width).map(move |col| (row, col)))
+        .map(|(row, col)| match board.contains(row, col) {
+            false => Some(Region::Gap),
+            true if content((row, col)).is_mine() => Some(Region::Mine),
+            true => None,
+        })
+        .collect();
+
+    let mut openings = 0;
+    for start in 0..regions.len() {
+        let position = (start / width, start % width);
+        if regions[start].is_some() || content(position) != Cell::Adjacent(0) {
+            continue;
+        }
+        let region = Region::Opening(openings);
+        openings += 1;
+        regions[start] = Some(region);
+        let mut queue = VecDeque::from([position]);
+        while let Some((row, col)) = queue.pop_front() {
+            for (r, c) in board.neighbors(row, col) {
+                let slot = &mut regions[r * width + c];
+                if slot.is_some() {
+                    continue;
+                }
+                *slot = Some(region);
+                if content((r, c)) == Cell::Adjacent(0) {
+                    queue.push_back((r, c));
+                }
+            }
+        }
+    }
+
+    let mut islands = 0;
+    let mut island_cells = 0;
+    for start in 0..regions.len() {
+        if regions[start].is_some() {
+            continue;
+        }
+        let region = Region::Island(islands);
+        islands += 1;
+        regions[start] = Some(region);
+        let mut queue = VecDeque::from([(start / width, start % width)]);
+        while let Some((row, col)) = queue.pop_front() {
+            island_cells += 1;
+            for (r, c) in board.neighbors(row, col) {
+                let slot = &mut regions[r * width + c];
+                if slot.is_none() {
+                    *slot = Some(region);
+                    queue.push_back((r, c));
+                }
+            }
+        }
+    }
+
+    Some(Analysis {
+        width,
+        regions: regions
+            .into_iter()
+            .map(|region| region.expect("every slot is a gap, a mine, an opening or an island"))
+            .collect(),
+        openings,
+        islands,
+        three_bv: openings + island_cells,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::game::fixtures::layout;
+    use crate::game::GameResult;
+    use crate::save;
+
+    #[test]
+    fn one_blank_region_and_its_border_take_a_single_click() {
+        let board = layout(&["*...", "....", "....", "...*"]);
+        let analysis = analyze(&board).expect("seeded");
+        assert_eq!(analysis.openings(), 1);
+        assert_eq!(analysis.islands(), 0);
+        assert_eq!(analysis.three_bv(), 1);
+        assert_eq!(analysis.region(0, 1), Region::Opening(0));
+        assert_eq!(analysis.region(3, 3), Region::Mine);
+    }
+
+    #[test]
+    fn a_number_walled_off_from_every_blank_is_an_island() {
+        let board = layout(&["..*..", "..*..", ".....", "..*..", "..*.."]);
+        let analysis = analyze(&board).expect("seeded");
+        assert_eq!(analysis.openings(), 2);
+        assert_eq!(analysis.islands(), 1);
+        assert_eq!(analysis.region(2, 2), Region::Island(0));
+        assert_eq!(analysis.region(2, 1), Region::Opening(0));
+        assert_eq!(analysis.region(2, 3), Region::Opening(1));
+        assert_eq!(analysis.three_bv(), 3);
+    }
+
+    #[test]
+    fn a_board_without_blanks_costs_one_click_per_safe_cell() {
+        let board = layout(&["*.*", "...", "*.*"]);
+        let analysis = analyze(&board).expect("seeded");
+        assert_eq!(analysis.openings(), 0);
+        assert_eq!(analysis.islands(), 1);
+        assert_eq!(analysis.three_bv(), 5);
+    }
+
+    #[test]
+    fn a_number_between_two_openings_is_counted_once() {
+        let board = layout(&["..*", "...", "*.."]);
+        let analysis = analyze(&board).expect("seeded");
+        assert_eq!(analysis.openings(), 2);
+        assert_eq!(analysis.region(1, 1), Region::Opening(0));
+        assert_eq!(analysis.region(1, 2), Region::Opening(1));
+        assert_eq!(analysis.three_bv(), 2);
+    }
+
+    #[test]
+    fn an_unseeded_board_has_nothing_to_measure() {
+        let config = Config::new(9, 9, 10).expect("valid config");
+        assert_eq!(analyze(&Board::new(config.clone())), None);
+        assert_eq!(analyze_ended(&GameState::new(config)), None);
+    }
+
+    #[test]
+    fn a_game_in_play_is_not_measured_until_it_ends() {
+        let board = layout(&["*...", "....", "....", "...*"]);
+        let mut state = GameState {
+            board,
+            result: None,
+        };
+        assert_eq!(analyze_ended(&state), None);
+        state.result = Some(GameResult::Won);
+        assert_eq!(analyze_ended(&state), analyze(&state.board));
+        assert!(analyze_ended(&state).is_some());
+    }
+
+    #[test]
+    fn a_layout_read_back_from_a_save_is_measured_mid_game() {
+        let playing = GameState {
+            board: layout(&["*...", "o...", "....", "...*"]),
+            result: None,
+        };
+        let loaded = save::read(&save::write(&playing)).expect("a save reads back");
+        assert!(!loaded.is_over());
+        let analysis = analyze(&loaded.board).expect("seeded");
+        assert_eq!(analysis.three_bv(), 1);
+    }
+
+    #[test]
+    fn a_gap_splits_the_board_and_costs_no_click() {
+        let board = layout(&["..-..", "..-..", "*.-.."]);
+        let analysis = analyze(&board).expect("seeded");
+        assert_eq!(analysis.region(0, 2), Region::Gap);
+        assert_eq!(analysis.openings(), 2);
+        // The number beside the mine touches no blank on its side of the gap.
+        assert_eq!(analysis.region(2, 1), Region::Island(0));
+        assert_eq!(analysis.three_bv(), 3);
+    }
+}
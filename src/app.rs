@@ -1,21 +1,155 @@
 //! Yew view layer.
 //!
-//! Contains no rules: every transition is `GameState::apply`, and the component
-//! only turns the resulting board into DOM nodes.
+//! Contains no rules: every transition is `History::apply` over
+//! `GameState::apply`, and the component only turns the resulting board into
+//! DOM nodes.
 
 use std::rc::Rc;
 
-use rand::thread_rng;
+use gloo_timers::callback::Interval;
+use rand::{thread_rng, Rng};
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
-use crate::config::{Config, ConfigError};
-use crate::game::{Action, Board, Cell, CellState, GameResult, GameState};
+use crate::analysis;
+use crate::clock::{Clock, Stopwatch};
+use crate::config::{Config, ConfigError, Grid};
+use crate::daily::{self, Completion, Date};
+use crate::game::{Action, Board, GameResult, GameState, PlayerView};
+use crate::generator::Version;
+use crate::infinite::{Tile, World};
+use crate::replay::Session;
+use crate::save;
+use crate::share::ShareCode;
 
-impl Reducible for GameState {
-    type Action = Action;
+/// `localStorage` key holding the game in progress as [`save`] text.
+const SAVE_KEY: &str = "minesweeper.save";
+
+/// `localStorage` key holding how the last daily challenge ended.
+const DAILY_KEY: &str = "minesweeper.daily";
+
+/// `MouseEvent::button` value for the wheel / middle button.
+const MIDDLE_BUTTON: i16 = 1;
+
+/// How often a running timer is redrawn, in milliseconds.
+const TICK: u32 = 1_000;
+
+/// Rows and columns of the endless board on screen at once.
+const VIEW_ROWS: i64 = 16;
+const VIEW_COLS: i64 = 24;
+
+/// How far one press of a pan button or arrow key moves the view.
+const PAN: i64 = 4;
+
+/// What the page asks of the session: a move in the current game, or a new
+/// game on the board a share code names.
+enum Command {
+    Play(Action),
+    Open(ShareCode),
+}
+
+/// Each game is recorded, so the reducer stamps every action with the time.
+impl Reducible for Session {
+    type Action = Command;
+
+    fn reduce(self: Rc<Self>, command: Self::Action) -> Rc<Self> {
+        Rc::new(match command {
+            Command::Play(action) => self.apply(action, &PerformanceClock),
+            Command::Open(code) => Session::from_code(code, &PerformanceClock),
+        })
+    }
+}
+
+/// What the endless view asks of its world.
+enum Move {
+    Reveal(i64, i64),
+    Flag(i64, i64),
+    Chord(i64, i64),
+    /// A fresh world with this seed and the same density.
+    Restart(u64),
+}
+
+impl Reducible for World {
+    type Action = Move;
 
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
-        Rc::new(self.apply(action, &mut thread_rng()))
+        let mut world = (*self).clone();
+        match action {
+            Move::Reveal(row, col) => world.reveal(row, col),
+            Move::Flag(row, col) => world.flag(row, col),
+            Move::Chord(row, col) => world.chord(row, col),
+            Move::Restart(seed) => {
+                world = World::new(seed, self.density()).expect("the density was checked")
+            }
+        }
+        Rc::new(world)
+    }
+}
+
+/// `performance.now()`, which unlike `Date.now()` cannot jump when the system
+/// clock is adjusted mid-game.
+struct PerformanceClock;
+
+impl Clock for PerformanceClock {
+    fn now(&self) -> u64 {
+        web_sys::window()
+            .and_then(|window| window.performance())
+            .map_or(0.0, |performance| performance.now()) as u64
+    }
+}
+
+/// Storage can be missing or refused (private windows, disabled cookies); the
+/// game then simply does not survive a reload.
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// The unfinished game from the last visit, if it was played under the same
+/// build settings. Anything unreadable is ignored and a new game begins.
+fn load(config: &Config) -> Option<GameState> {
+    let text = storage()?.get_item(SAVE_KEY).ok()??;
+    let state = save::read(&text).ok()?;
+    (state.board.config() == config && !state.is_over()).then_some(state)
+}
+
+/// A share code in the page URL, after the `#`, so a link opens its board.
+/// A code that does not read is ignored like a stale save.
+fn linked_code() -> Option<ShareCode> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    hash.strip_prefix('#')?.parse().ok()
+}
+
+/// A share code, or a bare seed to play on this build's own board.
+fn read_code(text: &str, config: &Config) -> Result<ShareCode, String> {
+    if let Ok(seed) = text.trim().parse() {
+        return Ok(ShareCode {
+            config: config.clone(),
+            seed,
+            generator: Version::LATEST,
+        });
+    }
+    text.parse::<ShareCode>().map_err(|error| error.to_string())
+}
+
+fn store(state: &GameState) {
+    if let Some(storage) = storage() {
+        // Best effort: a full or blocked store only costs the resume.
+        let _ = storage.set_item(SAVE_KEY, &save::write(state));
+    }
+}
+
+/// The UTC day by the wall clock, which decides the daily board.
+fn today() -> Date {
+    Date::from_unix_millis(js_sys::Date::now() as u64)
+}
+
+fn completion() -> Option<Completion> {
+    storage()?.get_item(DAILY_KEY).ok()??.parse().ok()
+}
+
+fn record(done: &Completion) {
+    if let Some(storage) = storage() {
+        let _ = storage.set_item(DAILY_KEY, &done.to_string());
     }
 }
 
@@ -39,13 +173,23 @@ pub struct Props {
     /// Carried as a `Result` so a bad build variable is reported on the page
     /// rather than swallowed or defaulted away.
     pub config: Result<Config, ConfigError>,
+    /// The endless board's mine density, in percent.
+    pub density: Result<usize, ConfigError>,
 }
 
+/// Either the configured board or the endless one; the toolbar of each
+/// switches to the other.
 #[function_component(App)]
 pub fn app(props: &Props) -> Html {
-    match &props.config {
-        Ok(config) => html! { <Game config={*config} /> },
-        Err(error) => html! {
+    let endless = use_state(|| false);
+    let on_switch = {
+        let endless = endless.clone();
+        Callback::from(move |()| endless.set(!*endless))
+    };
+    match (&props.config, &props.density) {
+        (Ok(_), Ok(density)) if *endless => html! { <Endless density={*density} {on_switch} /> },
+        (Ok(config), Ok(_)) => html! { <Game config={config.clone()} {on_switch} /> },
+        (Err(error), _) | (_, Err(error)) => html! {
             <main><p class="fatal">{ format!("configuration error: {error}") }</p></main>
         },
     }
@@ -54,41 +198,179 @@ pub fn app(props: &Props) -> Html {
 #[derive(Properties, PartialEq)]
 struct GameProps {
     config: Config,
+    on_switch: Callback<()>,
 }
 
 #[function_component(Game)]
 fn game(props: &GameProps) -> Html {
-    let config = props.config;
-    let state = use_reducer(move || GameState::new(config));
+    let config = props.config.clone();
+    // A linked board wins over the game in progress: following a link is a
+    // request for that board.
+    let state = use_reducer(move || match (linked_code(), load(&config)) {
+        (Some(code), _) => Session::from_code(code, &PerformanceClock),
+        (None, Some(saved)) => Session::resume(saved, thread_rng().gen(), &PerformanceClock),
+        (None, None) => Session::new(config, thread_rng().gen(), &PerformanceClock),
+    });
+    use_effect_with_deps(
+        |present| {
+            store(present);
+            || ()
+        },
+        state.history().present().clone(),
+    );
+    // The daily board is recognised by its code, so it counts however it was
+    // opened; only its first result of the day is kept.
+    let today = today();
+    let completed = use_state(completion);
+    let daily = state.replay().map(|replay| replay.code()) == Some(daily::challenge(today));
+    use_effect_with_deps(
+        {
+            let completed = completed.clone();
+            move |&(result, date): &(Option<GameResult>, Option<Date>)| {
+                let already = completed.map_or(false, |done| Some(done.date) == date);
+                if let (Some(result), Some(date), false) = (result, date, already) {
+                    let done = Completion { date, result };
+                    record(&done);
+                    completed.set(Some(done));
+                }
+                || ()
+            }
+        },
+        (state.history().present().result, daily.then_some(today)),
+    );
     let mode = use_state(|| Mode::Uncover);
+    let layer = use_state(|| 0_usize);
     let on_cell = {
         let (state, mode) = (state.clone(), *mode);
-        Callback::from(move |(row, col)| state.dispatch(mode.action(row, col)))
+        Callback::from(move |(row, col)| state.dispatch(Command::Play(mode.action(row, col))))
+    };
+    let on_chord = {
+        let state = state.clone();
+        Callback::from(move |(row, col)| state.dispatch(Command::Play(Action::Chord(row, col))))
+    };
+    let on_open = {
+        let state = state.clone();
+        Callback::from(move |code: ShareCode| state.dispatch(Command::Open(code)))
     };
+    let onkeydown = {
+        let state = state.clone();
+        Callback::from(move |event: KeyboardEvent| {
+            if let Some(action) = shortcut(&event) {
+                event.prevent_default();
+                state.dispatch(Command::Play(action));
+            }
+        })
+    };
+    let present = state.history().present();
+    // A linked board may have fewer layers than the one the tab was picked on.
+    let depth = present.board.config().depth;
+    let shown = (*layer).min(depth - 1);
+    // Focusable so the undo shortcuts reach it once the player clicks the board.
     html! {
-        <main>
+        <main tabindex="0" {onkeydown}>
             <h1>{ "Minesweeper" }</h1>
-            { toolbar(&mode, &state) }
+            { toolbar(&mode, &state, today, completed.filter(|done| done.is_for(today)), &props.on_switch) }
+            <CodeEntry config={props.config.clone()} {on_open} />
+            <Timer stopwatch={state.stopwatch()} />
             { status(&state) }
-            { grid(&state.board, &on_cell) }
+            { layers(depth, &layer) }
+            { grid(&present.board, shown, &on_cell, &on_chord) }
+            { replay(&state) }
         </main>
     }
 }
 
-fn toolbar(mode: &UseStateHandle<Mode>, state: &UseReducerHandle<GameState>) -> Html {
-    let onclick = {
-        let state = state.clone();
-        Callback::from(move |_: MouseEvent| state.dispatch(Action::Restart))
-    };
+#[derive(Properties, PartialEq)]
+struct TimerProps {
+    stopwatch: Stopwatch,
+}
+
+/// Whole seconds on the game clock. Only this component redraws on the tick,
+/// and only while the stopwatch runs.
+#[function_component(Timer)]
+fn timer(props: &TimerProps) -> Html {
+    let tick = use_state(|| 0_u64);
+    use_effect_with_deps(
+        move |&running| {
+            let interval =
+                running.then(|| Interval::new(TICK, move || tick.set(PerformanceClock.now())));
+            move || drop(interval)
+        },
+        props.stopwatch.is_running(),
+    );
+    let seconds = props.stopwatch.elapsed(PerformanceClock.now()) / 1_000;
+    html! { <p class="timer">{ format!("{seconds:03}") }</p> }
+}
+
+/// Ctrl+Z undoes, Ctrl+Y or Ctrl+Shift+Z redoes; Cmd stands in for Ctrl on a Mac.
+fn shortcut(event: &KeyboardEvent) -> Option<Action> {
+    if !(event.ctrl_key() || event.meta_key()) {
+        return None;
+    }
+    match (event.key().to_lowercase().as_str(), event.shift_key()) {
+        ("z", false) => Some(Action::Undo),
+        ("z", true) | ("y", _) => Some(Action::Redo),
+        _ => None,
+    }
+}
+
+fn toolbar(
+    mode: &UseStateHandle<Mode>,
+    state: &UseReducerHandle<Session>,
+    today: Date,
+    done: Option<Completion>,
+    on_switch: &Callback<()>,
+) -> Html {
+    let history = state.history();
     html! {
         <div class="toolbar">
             { mode_button("Uncover", Mode::Uncover, mode) }
             { mode_button("Flag", Mode::Flag, mode) }
-            <button {onclick} class="mode">{ "New game" }</button>
+            { action_button("Undo", Action::Undo, history.can_undo(), state) }
+            { action_button("Redo", Action::Redo, history.can_redo(), state) }
+            { action_button("New game", Action::Restart, true, state) }
+            { daily_button(today, done, state) }
+            { switch_button("Endless", on_switch) }
         </div>
     }
 }
 
+/// Opens today's challenge; once it is over for the day the button says so,
+/// though the board can still be played again.
+fn daily_button(today: Date, done: Option<Completion>, state: &UseReducerHandle<Session>) -> Html {
+    let onclick = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(Command::Open(daily::challenge(today))))
+    };
+    let (label, title) = match done.map(|done| done.result) {
+        Some(GameResult::Won) => ("Daily ✓", format!("{today}: swept")),
+        Some(GameResult::Lost) => ("Daily ✗", format!("{today}: boom")),
+        None => (
+            "Daily",
+            format!("{today}: the same board for everyone today"),
+        ),
+    };
+    html! { <button {onclick} class="mode" {title}>{ label }</button> }
+}
+
+fn action_button(
+    label: &str,
+    action: Action,
+    enabled: bool,
+    state: &UseReducerHandle<Session>,
+) -> Html {
+    let onclick = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(Command::Play(action)))
+    };
+    html! { <button {onclick} class="mode" disabled={!enabled}>{ label }</button> }
+}
+
+fn switch_button(label: &str, on_switch: &Callback<()>) -> Html {
+    let onclick = on_switch.reform(|_: MouseEvent| ());
+    html! { <button {onclick} class="mode">{ label }</button> }
+}
+
 fn mode_button(label: &str, target: Mode, mode: &UseStateHandle<Mode>) -> Html {
     let class = match **mode == target {
         true => "mode selected",
@@ -101,55 +383,358 @@ fn mode_button(label: &str, target: Mode, mode: &UseStateHandle<Mode>) -> Html {
     html! { <button {onclick} {class}>{ label }</button> }
 }
 
-fn status(state: &GameState) -> Html {
-    let (class, text) = match state.result {
-        Some(GameResult::Won) => ("status won", "Swept.".to_owned()),
-        Some(GameResult::Lost) => ("status lost", "Boom.".to_owned()),
-        None => (
+/// After the game, the board's 3BV says how hard it was to clear. A game
+/// with lives to spend counts them down beside the mines. A first click a
+/// no-guess board found no layout for says so instead. A recorded game ends
+/// the line with its share code, as a link to the same board.
+fn status(session: &Session) -> Html {
+    let state = session.history().present();
+    let three_bv = || match analysis::analyze_ended(state) {
+        Some(analysis) => format!(" 3BV {}.", analysis.three_bv()),
+        None => String::new(),
+    };
+    let (class, text) = match (state.result, session.failure()) {
+        (_, Some(error)) => ("status lost", error.to_string()),
+        (Some(GameResult::Won), None) => ("status won", format!("Swept.{}", three_bv())),
+        (Some(GameResult::Lost), None) => ("status lost", format!("Boom.{}", three_bv())),
+        (None, None) if state.board.config().lives > 1 => (
+            "status",
+            match state.board.lives_remaining() {
+                1 => format!("{} mines left, last life", state.board.mines_remaining()),
+                lives => format!(
+                    "{} mines left, {lives} lives left",
+                    state.board.mines_remaining()
+                ),
+            },
+        ),
+        (None, None) => (
             "status",
             format!("{} mines left", state.board.mines_remaining()),
         ),
     };
-    html! { <p {class}>{ text }</p> }
+    let code = match session.replay() {
+        Some(replay) => {
+            let code = replay.code().to_string();
+            html! { <>{ " Code " }<a class="code" href={format!("#{code}")}>{ code }</a></> }
+        }
+        None => html! {},
+    };
+    html! { <p {class}>{ text }{ code }</p> }
+}
+
+#[derive(Properties, PartialEq)]
+struct CodeEntryProps {
+    config: Config,
+    on_open: Callback<ShareCode>,
+}
+
+/// A box for a teammate's share code, or a bare seed, and the button that
+/// opens its board. A code that does not read says why instead.
+#[function_component(CodeEntry)]
+fn code_entry(props: &CodeEntryProps) -> Html {
+    let text = use_state(String::new);
+    let error = use_state(|| None::<String>);
+    let oninput = {
+        let text = text.clone();
+        Callback::from(move |event: InputEvent| {
+            text.set(event.target_unchecked_into::<HtmlInputElement>().value())
+        })
+    };
+    let onclick = {
+        let (text, error) = (text.clone(), error.clone());
+        let (config, on_open) = (props.config.clone(), props.on_open.clone());
+        Callback::from(move |_: MouseEvent| match read_code(&text, &config) {
+            Ok(code) => {
+                error.set(None);
+                on_open.emit(code);
+            }
+            Err(why) => error.set(Some(why)),
+        })
+    };
+    let message = match &*error {
+        Some(why) => html! { <span class="code-error">{ why }</span> },
+        None => html! {},
+    };
+    html! {
+        <div class="toolbar share">
+            <input class="code" placeholder="Share code or seed" value={(*text).clone()} {oninput} />
+            <button {onclick} class="mode" disabled={text.trim().is_empty()}>{ "Open" }</button>
+            { message }
+        </div>
+    }
+}
+
+/// Once the game is over, the recording is offered as text to copy. A game
+/// resumed from a save has none.
+fn replay(session: &Session) -> Html {
+    match session.replay() {
+        Some(replay) if session.history().present().is_over() => html! {
+            <details class="replay">
+                <summary>{ "Replay" }</summary>
+                <pre>{ replay.to_string() }</pre>
+            </details>
+        },
+        _ => html! {},
+    }
 }
 
-fn grid(board: &Board, on_cell: &Callback<(usize, usize)>) -> Html {
-    board
-        .rows()
+/// A cube is played one layer at a time; these tabs pick which one is shown.
+/// A flat board has a single layer and no tabs.
+fn layers(depth: usize, layer: &UseStateHandle<usize>) -> Html {
+    if depth < 2 {
+        return html! {};
+    }
+    let tabs: Html = (0..depth)
+        .map(|target| {
+            let class = match **layer == target {
+                true => "mode selected",
+                false => "mode",
+            };
+            let onclick = {
+                let layer = layer.clone();
+                Callback::from(move |_: MouseEvent| layer.set(target))
+            };
+            html! { <button {onclick} {class}>{ format!("Layer {}", target + 1) }</button> }
+        })
+        .collect();
+    html! { <div class="toolbar layers">{ tabs }</div> }
+}
+
+/// Draws one layer of the board; rows keep their board-wide index so actions
+/// address the right cell. Hex boards shift every odd row half a cell right,
+/// matching the offset rows the rules count neighbours by.
+fn grid(
+    board: &Board,
+    layer: usize,
+    on_cell: &Callback<(usize, usize)>,
+    on_chord: &Callback<(usize, usize)>,
+) -> Html {
+    let config = board.config();
+    let hex = config.grid == Grid::Hex;
+    let rows: Html = board
+        .view_rows()
         .enumerate()
-        .map(|(row, cells)| {
-            let cells: Html = cells
-                .iter()
+        .skip(layer * config.height)
+        .take(config.height)
+        .map(|(row, views)| {
+            let cells: Html = views
+                .into_iter()
                 .enumerate()
-                .map(|(col, cell)| cell_view(row, col, cell, on_cell))
+                .map(|(col, view)| cell_view(row, col, view, on_cell, on_chord))
                 .collect();
-            html! { <div class="row">{ cells }</div> }
+            let class = match hex && row % config.height % 2 == 1 {
+                true => "row offset",
+                false => "row",
+            };
+            html! { <div {class}>{ cells }</div> }
         })
-        .collect()
+        .collect();
+    let class = match hex {
+        true => "board hex",
+        false => "board",
+    };
+    html! { <div {class}>{ rows }</div> }
 }
 
-fn cell_view(row: usize, col: usize, cell: &CellState, on_cell: &Callback<(usize, usize)>) -> Html {
+/// A plain click follows the selected mode; a double-click or a middle-click
+/// chords, as in the desktop game. A gap in the mask keeps its slot
+/// in the row, so the cells around it stay aligned, but shows and does nothing.
+fn cell_view(
+    row: usize,
+    col: usize,
+    view: PlayerView,
+    on_cell: &Callback<(usize, usize)>,
+    on_chord: &Callback<(usize, usize)>,
+) -> Html {
+    if view == PlayerView::Gap {
+        return html! { <div class="cell gap" /> };
+    }
     let onclick = {
         let on_cell = on_cell.clone();
         Callback::from(move |_: MouseEvent| on_cell.emit((row, col)))
     };
-    html! { <div {onclick} class={cell_class(cell)}>{ cell_face(cell) }</div> }
+    let ondblclick = {
+        let on_chord = on_chord.clone();
+        Callback::from(move |_: MouseEvent| on_chord.emit((row, col)))
+    };
+    let onauxclick = {
+        let on_chord = on_chord.clone();
+        Callback::from(move |event: MouseEvent| {
+            if event.button() == MIDDLE_BUTTON {
+                on_chord.emit((row, col));
+            }
+        })
+    };
+    html! {
+        <div {onclick} {ondblclick} {onauxclick} class={cell_class(view)}>
+            { cell_face(view) }
+        </div>
+    }
 }
 
-fn cell_class(cell: &CellState) -> &'static str {
-    match (cell.uncovered, cell.content) {
-        (false, _) => "cell covered",
-        (true, Cell::Mine) => "cell mine",
-        (true, _) => "cell uncovered",
+fn cell_class(view: PlayerView) -> &'static str {
+    match view {
+        PlayerView::Mine(_) => "cell mine",
+        view if view.is_covered() => "cell covered",
+        _ => "cell uncovered",
     }
 }
 
-fn cell_face(cell: &CellState) -> Html {
-    match (cell.uncovered, cell.flagged, cell.content) {
-        (false, true, _) => html! { "\u{1F6A9}" },
-        (false, false, _) => html! {},
-        (true, _, Cell::Mine) => html! { "\u{1F4A3}" },
-        (true, _, Cell::Adjacent(0)) => html! {},
-        (true, _, Cell::Adjacent(count)) => html! { count },
+/// Flags and mines show their count beside them once it is more than one.
+fn cell_face(view: PlayerView) -> Html {
+    match view {
+        PlayerView::Flagged(1) => html! { "\u{1F6A9}" },
+        PlayerView::Flagged(count) => html! { format!("\u{1F6A9}{count}") },
+        PlayerView::Questioned => html! { "?" },
+        PlayerView::Gap | PlayerView::Covered | PlayerView::Number(0) => html! {},
+        PlayerView::Mine(1) => html! { "\u{1F4A3}" },
+        PlayerView::Mine(count) => html! { format!("\u{1F4A3}{count}") },
+        PlayerView::Number(count) => html! { count },
     }
 }
+
+#[derive(Properties, PartialEq)]
+struct EndlessProps {
+    density: usize,
+    on_switch: Callback<()>,
+}
+
+/// The endless board through a window of [`VIEW_ROWS`] by [`VIEW_COLS`]
+/// cells. The buttons, the arrow keys and the mouse wheel move the window;
+/// the world draws whatever comes into it.
+#[function_component(Endless)]
+fn endless(props: &EndlessProps) -> Html {
+    let density = props.density;
+    let world = use_reducer(move || {
+        World::new(thread_rng().gen(), density).expect("the density was checked")
+    });
+    let mode = use_state(|| Mode::Uncover);
+    // The top-left cell on screen, starting with the origin in the middle.
+    let corner = use_state(|| (-VIEW_ROWS / 2, -VIEW_COLS / 2));
+    let on_pan = {
+        let corner = corner.clone();
+        Callback::from(move |(rows, cols): (i64, i64)| {
+            corner.set((corner.0 + rows, corner.1 + cols))
+        })
+    };
+    let on_cell = {
+        let (world, mode) = (world.clone(), *mode);
+        Callback::from(move |(row, col)| {
+            world.dispatch(match mode {
+                Mode::Uncover => Move::Reveal(row, col),
+                Mode::Flag => Move::Flag(row, col),
+            })
+        })
+    };
+    let on_chord = {
+        let world = world.clone();
+        Callback::from(move |(row, col)| world.dispatch(Move::Chord(row, col)))
+    };
+    let onkeydown = {
+        let on_pan = on_pan.clone();
+        Callback::from(move |event: KeyboardEvent| {
+            let step = match event.key().as_str() {
+                "ArrowUp" => (-PAN, 0),
+                "ArrowDown" => (PAN, 0),
+                "ArrowLeft" => (0, -PAN),
+                "ArrowRight" => (0, PAN),
+                _ => return,
+            };
+            event.prevent_default();
+            on_pan.emit(step);
+        })
+    };
+    // Shift turns the wheel sideways, as it does in most scrolling views.
+    // Yew listens to the wheel passively, so the page cannot be held still.
+    let onwheel = on_pan.reform(|event: WheelEvent| {
+        let (down, across) = (notch(event.delta_y()), notch(event.delta_x()));
+        match event.shift_key() {
+            true => (across, down),
+            false => (down, across),
+        }
+    });
+    let restart = {
+        let world = world.clone();
+        Callback::from(move |_: MouseEvent| world.dispatch(Move::Restart(thread_rng().gen())))
+    };
+    let (class, text) = match world.is_over() {
+        true => ("status lost", format!("Boom. Score {}.", world.score())),
+        false => ("status", format!("Score {}", world.score())),
+    };
+    html! {
+        <main tabindex="0" {onkeydown}>
+            <h1>{ "Minesweeper" }</h1>
+            <div class="toolbar">
+                { mode_button("Uncover", Mode::Uncover, &mode) }
+                { mode_button("Flag", Mode::Flag, &mode) }
+                <button onclick={restart} class="mode">{ "New world" }</button>
+                { switch_button("Classic", &props.on_switch) }
+            </div>
+            <div class="toolbar pan">
+                { pan_button("\u{2190}", (0, -PAN), &on_pan) }
+                { pan_button("\u{2191}", (-PAN, 0), &on_pan) }
+                { pan_button("\u{2193}", (PAN, 0), &on_pan) }
+                { pan_button("\u{2192}", (0, PAN), &on_pan) }
+            </div>
+            <p {class}>{ text }</p>
+            <div class="board endless" {onwheel}>
+                { endless_rows(&world, *corner, &on_cell, &on_chord) }
+            </div>
+        </main>
+    }
+}
+
+/// One cell per wheel event whichever way it turned. `f64::signum` would
+/// turn an untouched axis, a delta of zero, into a step.
+fn notch(delta: f64) -> i64 {
+    i64::from(delta > 0.0) - i64::from(delta < 0.0)
+}
+
+fn pan_button(label: &str, step: (i64, i64), on_pan: &Callback<(i64, i64)>) -> Html {
+    let onclick = on_pan.reform(move |_: MouseEvent| step);
+    html! { <button {onclick} class="mode">{ label }</button> }
+}
+
+fn endless_rows(
+    world: &World,
+    (top, left): (i64, i64),
+    on_cell: &Callback<(i64, i64)>,
+    on_chord: &Callback<(i64, i64)>,
+) -> Html {
+    (top..top + VIEW_ROWS)
+        .map(|row| {
+            let tiles: Html = (left..left + VIEW_COLS)
+                .map(|col| tile_view(row, col, world.tile(row, col), on_cell, on_chord))
+                .collect();
+            html! { <div class="row">{ tiles }</div> }
+        })
+        .collect()
+}
+
+/// The endless board's counterpart of [`cell_view`], on signed coordinates.
+fn tile_view(
+    row: i64,
+    col: i64,
+    tile: Tile,
+    on_cell: &Callback<(i64, i64)>,
+    on_chord: &Callback<(i64, i64)>,
+) -> Html {
+    let onclick = on_cell.reform(move |_: MouseEvent| (row, col));
+    let ondblclick = on_chord.reform(move |_: MouseEvent| (row, col));
+    let onauxclick = {
+        let on_chord = on_chord.clone();
+        Callback::from(move |event: MouseEvent| {
+            if event.button() == MIDDLE_BUTTON {
+                on_chord.emit((row, col));
+            }
+        })
+    };
+    let (class, face) = match tile {
+        Tile::Covered => ("cell covered", html! {}),
+        Tile::Flagged => ("cell covered", html! { "\u{1F6A9}" }),
+        Tile::Open(0) => ("cell uncovered", html! {}),
+        Tile::Open(count) => ("cell uncovered", html! { count }),
+        Tile::Exploded => ("cell mine", html! { "\u{1F4A3}" }),
+    };
+    html! { <div {onclick} {ondblclick} {onauxclick} {class}>{ face }</div> }
+}
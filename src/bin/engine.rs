@@ -0,0 +1,20 @@
+//! Plays minesweeper over stdin and stdout for bots in any language; the
+//! protocol is documented in `minesweeper::protocol`.
+
+use std::io::{self, BufRead, Write};
+
+use minesweeper::protocol::Engine;
+
+fn main() -> io::Result<()> {
+    let mut engine = Engine::default();
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let Some(answer) = engine.handle(&line?) else {
+            break;
+        };
+        // Flushed per answer: the bot waits on it before its next line.
+        writeln!(stdout, "{answer}")?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,170 @@
+//! Game time.
+//!
+//! The rules never read a clock themselves. A [`Clock`] is handed in from
+//! outside, so the browser can use `performance.now()` while tests step a
+//! [`ManualClock`] by exact amounts, and a [`Stopwatch`] turns the readings
+//! into the time a game has been running.
+
+use std::cell::Cell;
+
+use crate::game::GameState;
+
+/// A millisecond time source that never runs backwards. Only differences
+/// between readings are meaningful.
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+/// A clock that only moves when told to.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    now: Cell<u64>,
+}
+
+impl ManualClock {
+    pub fn new(now: u64) -> Self {
+        Self {
+            now: Cell::new(now),
+        }
+    }
+
+    pub fn advance(&self, millis: u64) {
+        self.now.set(self.now.get() + millis);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.now.get()
+    }
+}
+
+/// Runs from the first move that uncovers a cell until the game has a result.
+///
+/// A loss taken back with undo sets it running again as if it had never
+/// stopped, so the time spent looking at the loss still counts. Undoing back
+/// to a board with nothing uncovered resets it, and the next reveal starts it
+/// from zero.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stopwatch {
+    started: Option<u64>,
+    stopped: Option<u64>,
+}
+
+impl Stopwatch {
+    /// Brings the stopwatch up to date with `state`, read at `now`.
+    pub fn observe(self, state: &GameState, now: u64) -> Self {
+        let opened = state.board.rows().flatten().any(|cell| cell.uncovered);
+        let started = match opened {
+            true => self.started.or(Some(now)),
+            false => None,
+        };
+        let stopped = match (state.result, self.stopped) {
+            (Some(_), None) => started.map(|_| now),
+            (Some(_), stopped) => stopped,
+            (None, _) => None,
+        };
+        Self { started, stopped }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some() && self.stopped.is_none()
+    }
+
+    /// Milliseconds on the stopwatch at `now`; zero until the game starts.
+    pub fn elapsed(&self, now: u64) -> u64 {
+        match self.started {
+            Some(started) => self.stopped.unwrap_or(now).saturating_sub(started),
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, UndoPolicy};
+    use crate::game::fixtures::{layout, layout_with};
+    use crate::game::{Action, GameResult};
+    use crate::generator::Generator;
+    use crate::history::History;
+
+    fn play(state: &GameState, action: Action) -> GameState {
+        state.apply(action, &mut Generator::new(0))
+    }
+
+    #[test]
+    fn nothing_runs_before_the_first_cell_is_uncovered() {
+        let clock = ManualClock::new(500);
+        let state = GameState::new(Config::new(9, 9, 10).expect("valid config"));
+        let flagged = play(&state, Action::Flag(0, 0));
+        let stopwatch = Stopwatch::default().observe(&flagged, clock.now());
+        clock.advance(3_000);
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed(clock.now()), 0);
+    }
+
+    #[test]
+    fn time_runs_from_the_first_reveal_until_the_result() {
+        let clock = ManualClock::new(1_000);
+        let state = GameState {
+            board: layout(&["*.", ".."]),
+            result: None,
+        };
+        let opened = play(&state, Action::Reveal(1, 1));
+        assert_eq!(opened.result, None);
+        let stopwatch = Stopwatch::default().observe(&opened, clock.now());
+        clock.advance(2_500);
+        assert!(stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed(clock.now()), 2_500);
+
+        let won = play(&play(&opened, Action::Reveal(0, 1)), Action::Reveal(1, 0));
+        assert_eq!(won.result, Some(GameResult::Won));
+        let stopwatch = stopwatch.observe(&won, clock.now());
+        clock.advance(10_000);
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed(clock.now()), 2_500);
+    }
+
+    #[test]
+    fn a_loss_taken_back_starts_the_clock_again() {
+        let clock = ManualClock::new(0);
+        let state = GameState {
+            board: layout_with(&["*.", ".."], |config| {
+                config.with_undo(UndoPolicy::AllowAfterLoss)
+            }),
+            result: None,
+        };
+        let opened = play(&state, Action::Reveal(1, 1));
+        let stopwatch = Stopwatch::default().observe(&opened, clock.now());
+        clock.advance(1_000);
+        let lost = play(&opened, Action::Reveal(0, 0));
+        let stopwatch = stopwatch.observe(&lost, clock.now());
+        clock.advance(1_000);
+        let stopwatch = stopwatch.observe(&opened, clock.now());
+        clock.advance(1_000);
+        assert!(stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed(clock.now()), 3_000);
+    }
+
+    #[test]
+    fn undoing_back_to_an_untouched_board_resets_the_clock() {
+        let clock = ManualClock::new(0);
+        let start: History = GameState {
+            board: layout(&["*..", "...", "..*"]),
+            result: None,
+        }
+        .into();
+        let opened = start.apply(Action::Reveal(0, 2), &mut Generator::new(0));
+        let stopwatch = Stopwatch::default().observe(opened.present(), clock.now());
+        clock.advance(4_000);
+        let undone = opened.apply(Action::Undo, &mut Generator::new(0));
+        let stopwatch = stopwatch.observe(undone.present(), clock.now());
+        clock.advance(4_000);
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed(clock.now()), 0);
+        let stopwatch = stopwatch.observe(opened.present(), clock.now());
+        clock.advance(500);
+        assert_eq!(stopwatch.elapsed(clock.now()), 500);
+    }
+}
@@ -6,31 +6,450 @@
 //! error instead of a silently clamped default.
 
 use std::fmt;
+use std::ops::RangeInclusive;
+use std::sync::Arc;
 
-/// The first click and its eight neighbours are guaranteed mine-free, so a
-/// board must keep that many cells in reserve.
-pub const SAFE_REGION: usize = 9;
+use crate::geometry;
 
 const DEFAULT_WIDTH: usize = 10;
 const DEFAULT_HEIGHT: usize = 10;
 const DEFAULT_MINES: usize = 15;
+const DEFAULT_DEPTH: usize = 1;
+const DEFAULT_MINES_PER_CELL: usize = 1;
+const DEFAULT_LIVES: usize = 1;
+const DEFAULT_DENSITY: usize = 18;
+
+/// Mine densities an endless board accepts, in percent. Below the floor a
+/// blank opens onto blanks often enough that one click could flood without
+/// end; above the ceiling there is little left to clear.
+pub const DENSITIES: RangeInclusive<usize> = 12..=40;
+
+/// The most mines one cell may hold: one digit in a saved picture.
+const MOST_MINES_PER_CELL: usize = 9;
+
+/// The most slots a board may have, every layer counted: 256 by 256. Sizes
+/// arrive from links and bot commands, and everything past the check walks
+/// every cell, so an absurd size is refused before anything is counted.
+pub const MOST_CELLS: usize = 1 << 16;
+
+/// The narrowest side a no-guess board may have. Thinner boards leave the
+/// solver so few numbers that some first clicks have no clearable layout.
+pub const LEAST_NO_GUESS_SIDE: usize = 4;
+
+/// The largest no-guess board. Each draw is played out by the solver, and
+/// the share of draws it can clear falls as boards grow.
+pub const MOST_NO_GUESS_CELLS: usize = 1024;
+
+/// The densest no-guess board, in percent of its cells: enough for the
+/// classic expert board's 99 mines in 480 cells. Past it solvable layouts
+/// grow too rare to find within [`NO_GUESS_ATTEMPTS`].
+///
+/// [`NO_GUESS_ATTEMPTS`]: crate::game::NO_GUESS_ATTEMPTS
+pub const MOST_NO_GUESS_DENSITY: usize = 21;
 
 const WIDTH_VAR: &str = "MINESWEEPER_WIDTH";
 const HEIGHT_VAR: &str = "MINESWEEPER_HEIGHT";
+const DEPTH_VAR: &str = "MINESWEEPER_DEPTH";
 const MINES_VAR: &str = "MINESWEEPER_MINES";
+const MINES_PER_CELL_VAR: &str = "MINESWEEPER_MINES_PER_CELL";
+const LIVES_VAR: &str = "MINESWEEPER_LIVES";
+const QUESTION_MARKS_VAR: &str = "MINESWEEPER_QUESTION_MARKS";
+const NO_GUESS_VAR: &str = "MINESWEEPER_NO_GUESS";
+const FIRST_CLICK_VAR: &str = "MINESWEEPER_FIRST_CLICK";
+const UNDO_LOSSES_VAR: &str = "MINESWEEPER_UNDO_LOSSES";
+const TOPOLOGY_VAR: &str = "MINESWEEPER_TOPOLOGY";
+const GRID_VAR: &str = "MINESWEEPER_GRID";
+const KERNEL_VAR: &str = "MINESWEEPER_KERNEL";
+const MASK_VAR: &str = "MINESWEEPER_MASK";
+const DENSITY_VAR: &str = "MINESWEEPER_DENSITY";
+
+/// Every variable that shapes the board, in the order `.env.example` lists
+/// them. `MINESWEEPER_DENSITY` is left out on purpose: it shapes the endless
+/// board, which no `Config` describes and neither the terminal nor the engine
+/// plays, so as a flag or a protocol setting it would be accepted and then
+/// ignored. [`var_named`] does not know it, and `density=18` is refused.
+pub const VARS: &[&str] = &[
+    WIDTH_VAR,
+    HEIGHT_VAR,
+    DEPTH_VAR,
+    MINES_VAR,
+    FIRST_CLICK_VAR,
+    MINES_PER_CELL_VAR,
+    LIVES_VAR,
+    QUESTION_MARKS_VAR,
+    NO_GUESS_VAR,
+    UNDO_LOSSES_VAR,
+    TOPOLOGY_VAR,
+    GRID_VAR,
+    KERNEL_VAR,
+    MASK_VAR,
+];
+
+const TOPOLOGIES: &[&str] = &["flat", "cylinder", "torus"];
+const GRIDS: &[&str] = &["square", "hex"];
+const FIRST_CLICKS: &[&str] = &["unprotected", "safe-cell", "opening", "relocate"];
+
+/// How mines are laid on the first reveal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Generation {
+    /// Uniformly at random outside the safe region. May leave forced guesses.
+    #[default]
+    Uniform,
+    /// Redrawn until the solver can clear the board from the opening click by
+    /// deduction alone.
+    NoGuess,
+}
+
+/// What the first reveal is promised. Whatever the policy, at least one cell
+/// stays free of mines, so every board can be won.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FirstClick {
+    /// No promise: the first click can hit a mine like any other.
+    Unprotected,
+    /// The clicked cell is never mined, but it may be a number.
+    SafeCell,
+    /// The clicked cell and its neighbours are never mined, so the click is
+    /// a blank that opens an area.
+    #[default]
+    Opening,
+    /// Mines are laid anywhere; if one lands under the click it is moved to
+    /// the first cell, reading from the top left, with room for it.
+    Relocate,
+}
+
+/// Whether a losing reveal can be taken back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UndoPolicy {
+    /// Mis-clicks can be undone, but a mine that went off stays off.
+    #[default]
+    LossIsFinal,
+    AllowAfterLoss,
+}
+
+/// Which edges of the grid join up. Across a joined edge the cells on the far
+/// side are neighbours, for counting, flood fill and the first-click guarantee
+/// alike.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    /// A plain rectangle: edge cells have fewer neighbours.
+    #[default]
+    Flat,
+    /// The left and right edges join.
+    Cylinder,
+    /// Both pairs of opposite edges join, so every cell has the same
+    /// neighbourhood.
+    Torus,
+}
+
+impl Topology {
+    pub fn wraps_columns(self) -> bool {
+        matches!(self, Topology::Cylinder | Topology::Torus)
+    }
+
+    pub fn wraps_rows(self) -> bool {
+        self == Topology::Torus
+    }
+}
+
+/// The shape of a cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Grid {
+    /// Eight neighbours.
+    #[default]
+    Square,
+    /// Six neighbours, with odd rows offset half a cell to the right.
+    Hex,
+}
+
+/// Which cells around a square cell are its neighbours: the ones its number
+/// counts, a blank opens, and the first click keeps clear.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Kernel {
+    /// The eight surrounding cells.
+    #[default]
+    Moore,
+    /// The four cells sharing an edge.
+    Orthogonal,
+    /// The eight cells a knight's move away.
+    Knight,
+    /// The 24 cells within two rows and two columns.
+    Radius2,
+    Custom(Offsets),
+}
+
+impl Kernel {
+    const NAMES: &'static [&'static str] = &["moore", "orthogonal", "knight", "radius-2"];
 
+    /// A name from `NAMES`, or a custom kernel's offsets as written by
+    /// `Display`.
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.trim() {
+            "moore" => Some(Kernel::Moore),
+            "orthogonal" => Some(Kernel::Orthogonal),
+            "knight" => Some(Kernel::Knight),
+            "radius-2" => Some(Kernel::Radius2),
+            offsets => Offsets::parse(offsets).map(Kernel::Custom),
+        }
+    }
+}
+
+impl fmt::Display for Kernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kernel::Moore => f.write_str("moore"),
+            Kernel::Orthogonal => f.write_str("orthogonal"),
+            Kernel::Knight => f.write_str("knight"),
+            Kernel::Radius2 => f.write_str("radius-2"),
+            Kernel::Custom(offsets) => offsets.fmt(f),
+        }
+    }
+}
+
+/// A non-empty set of `(row, column)` steps, each at most [`Offsets::REACH`]
+/// away along both axes and none of them the cell itself. Kept as one bit
+/// per cell of the window around the centre, so a kernel is a plain value.
+///
+/// Nothing requires the set to be symmetric: with only `(0, 1)`, each number
+/// counts the cell to its right, and so does not count the cell to its left.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Offsets(u128);
+
+impl Offsets {
+    pub const REACH: isize = 4;
+    const SPAN: isize = 2 * Self::REACH + 1;
+
+    /// `None` if the list is empty, contains `(0, 0)`, or steps too far.
+    pub fn new(steps: &[(isize, isize)]) -> Option<Self> {
+        let mut bits = 0;
+        for &(dr, dc) in steps {
+            let reachable = (-Self::REACH..=Self::REACH).contains(&dr)
+                && (-Self::REACH..=Self::REACH).contains(&dc);
+            if !reachable || (dr, dc) == (0, 0) {
+                return None;
+            }
+            bits |= 1 << ((dr + Self::REACH) * Self::SPAN + dc + Self::REACH);
+        }
+        (bits != 0).then_some(Self(bits))
+    }
+
+    /// The steps in row-major order.
+    pub fn iter(self) -> impl Iterator<Item = (isize, isize)> {
+        (0..Self::SPAN * Self::SPAN)
+            .filter(move |bit| self.0 & 1 << bit != 0)
+            .map(|bit| {
+                (
+                    bit / Self::SPAN - Self::REACH,
+                    bit % Self::SPAN - Self::REACH,
+                )
+            })
+    }
+
+    /// Space-separated `row,col` pairs, such as `-1,2 1,2`.
+    fn parse(text: &str) -> Option<Self> {
+        let steps: Option<Vec<_>> = text
+            .split_whitespace()
+            .map(|pair| {
+                let (dr, dc) = pair.split_once(',')?;
+                Some((dr.parse().ok()?, dc.parse().ok()?))
+            })
+            .collect();
+        Self::new(&steps?)
+    }
+}
+
+impl fmt::Display for Offsets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs: Vec<_> = self.iter().map(|(dr, dc)| format!("{dr},{dc}")).collect();
+        f.write_str(&pairs.join(" "))
+    }
+}
+
+/// Which cells of the rectangle exist, for boards shaped like a ring, a circle
+/// or a letter. Drawn as rows of `#` for a cell and `-` for a gap, one row
+/// per stored row, so a cube's mask draws every layer.
+///
+/// Shared rather than copied, since every state in the undo history holds
+/// the config.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    width: usize,
+    cells: Arc<[bool]>,
+}
+
+impl Mask {
+    pub const CELL: char = '#';
+    pub const GAP: char = '-';
+
+    /// `None` unless every row has the same width, every glyph is a cell or a
+    /// gap, and at least one is a cell.
+    pub fn from_rows(rows: &[&str]) -> Option<Self> {
+        let width = rows.first()?.chars().count();
+        let mut cells = Vec::with_capacity(width * rows.len());
+        for row in rows {
+            if row.chars().count() != width {
+                return None;
+            }
+            for glyph in row.chars() {
+                cells.push(match glyph {
+                    Self::CELL => true,
+                    Self::GAP => false,
+                    _ => return None,
+                });
+            }
+        }
+        cells.contains(&true).then(|| Self {
+            width,
+            cells: cells.into(),
+        })
+    }
+
+    /// Rows separated by whitespace, as `Display` writes them: `-#- ### -#-`.
+    pub fn parse(text: &str) -> Option<Self> {
+        Self::from_rows(&text.split_whitespace().collect::<Vec<_>>())
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.len() / self.width
+    }
+
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        self.cells[row * self.width + col]
+    }
+}
+
+impl fmt::Display for Mask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, row) in self.cells.chunks(self.width).enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            for &cell in row {
+                f.write_str(match cell {
+                    true => "#",
+                    false => "-",
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Config {
     pub width: usize,
     pub height: usize,
+    /// Layers stacked into a cube; 1 is an ordinary flat board. Each layer's
+    /// `height` rows follow the previous layer's, so a cell is still addressed
+    /// by a `(row, col)` pair with `row` running over every layer.
+    pub depth: usize,
     pub mines: usize,
+    /// How many mines one cell may hold. Above 1, numbers add up every mine
+    /// around them and a flag claims a count the player cycles through.
+    pub mines_per_cell: usize,
+    /// Mine hits the player survives, counting the one that ends the game: 1
+    /// is the classic rules. A hit that leaves a life over stays uncovered as
+    /// a known mine and play goes on.
+    pub lives: usize,
+    /// Whether marking a covered cell cycles flag -> question mark -> nothing,
+    /// rather than just toggling the flag.
+    pub question_marks: bool,
+    pub generation: Generation,
+    pub first_click: FirstClick,
+    pub undo: UndoPolicy,
+    pub topology: Topology,
+    pub grid: Grid,
+    /// Only square cells take a kernel; hex cells always have their six.
+    pub kernel: Kernel,
+    /// The cells that exist, when the board is not the full rectangle. A gap
+    /// is never mined, counted or revealed, and no cell's neighbours include
+    /// it.
+    pub mask: Option<Mask>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ConfigError {
-    NotANumber { var: &'static str, value: String },
-    ZeroDimension { var: &'static str },
-    TooManyMines { mines: usize, capacity: usize },
+    NotANumber {
+        var: &'static str,
+        value: String,
+    },
+    NotABool {
+        var: &'static str,
+        value: String,
+    },
+    NotOneOf {
+        var: &'static str,
+        value: String,
+        options: &'static [&'static str],
+    },
+    ZeroDimension {
+        var: &'static str,
+    },
+    NotAKernel {
+        value: String,
+    },
+    HexKernel {
+        kernel: Kernel,
+    },
+    NotAMask {
+        value: String,
+    },
+    /// The mask is drawn `width` by `rows` but the board is `expected_width`
+    /// by `expected_rows`, counting every layer's rows.
+    MaskSize {
+        width: usize,
+        rows: usize,
+        expected_width: usize,
+        expected_rows: usize,
+    },
+    /// Hex rows alternate their offset, so a torus that joined an even row to
+    /// an even row would put two cells next to each other on one side only.
+    UnevenHexTorus {
+        height: usize,
+    },
+    TooManyMines {
+        mines: usize,
+        capacity: usize,
+    },
+    MinesPerCell {
+        value: usize,
+    },
+    NoLives,
+    /// `width` by `height` by `depth` is more than [`MOST_CELLS`], or more
+    /// than a `usize` can count.
+    TooManyCells {
+        width: usize,
+        height: usize,
+        depth: usize,
+    },
+    /// An endless board's mine density, in percent, outside [`DENSITIES`].
+    Density {
+        value: usize,
+    },
+    /// The solver plays a no-guess board from the first click, which it
+    /// cannot do if that click may be a mine.
+    UnprotectedNoGuess,
+    /// The solver reasons about one mine per cell, so it cannot vouch for a
+    /// board that stacks them.
+    StackedNoGuess,
+    /// A mask, a stack of layers or a kernel other than the touching cells,
+    /// where some first clicks have no layout the solver clears.
+    ShapedNoGuess,
+    /// A no-guess board with a side under [`LEAST_NO_GUESS_SIDE`] or more than
+    /// [`MOST_NO_GUESS_CELLS`] cells.
+    NoGuessSize {
+        width: usize,
+        height: usize,
+    },
+    /// More mines than a no-guess board of this size can promise, past
+    /// [`MOST_NO_GUESS_DENSITY`] percent of its cells.
+    NoGuessDensity {
+        mines: usize,
+        most: usize,
+    },
 }
 
 impl fmt::Display for ConfigError {
@@ -39,11 +458,103 @@ impl fmt::Display for ConfigError {
             Self::NotANumber { var, value } => {
                 write!(f, "{var} must be a non-negative integer, got {value:?}")
             }
+            Self::NotABool { var, value } => {
+                write!(f, "{var} must be true or false, got {value:?}")
+            }
+            Self::NotOneOf {
+                var,
+                value,
+                options,
+            } => write!(
+                f,
+                "{var} must be one of {}, got {value:?}",
+                options.join(", ")
+            ),
+            Self::NotAKernel { value } => write!(
+                f,
+                "{KERNEL_VAR} must be one of {} or a list of row,col offsets \
+                 within {} of the cell, got {value:?}",
+                Kernel::NAMES.join(", "),
+                Offsets::REACH
+            ),
+            Self::HexKernel { kernel } => write!(
+                f,
+                "{KERNEL_VAR} is {kernel} but a hex grid always counts its six \
+                 neighbours; use {GRID_VAR}=square"
+            ),
+            Self::NotAMask { value } => write!(
+                f,
+                "{MASK_VAR} must be rows of '#' (a cell) and '-' (a gap), all \
+                 the same width and separated by spaces, got {value:?}"
+            ),
+            Self::MaskSize {
+                width,
+                rows,
+                expected_width,
+                expected_rows,
+            } => write!(
+                f,
+                "{MASK_VAR} is {width} wide and {rows} rows tall but the board is \
+                 {expected_width} by {expected_rows} ({HEIGHT_VAR} rows per layer)"
+            ),
             Self::ZeroDimension { var } => write!(f, "{var} must be at least 1"),
+            Self::UnevenHexTorus { height } => write!(
+                f,
+                "a hex torus needs an even {HEIGHT_VAR} so the offset rows line up \
+                 across the wrap, got {height}"
+            ),
             Self::TooManyMines { mines, capacity } => write!(
                 f,
-                "{MINES_VAR} is {mines} but only {capacity} cells can hold a mine \
-                 (the first click and its 8 neighbours stay clear)"
+                "{MINES_VAR} is {mines} but only {capacity} mines fit \
+                 (the {FIRST_CLICK_VAR} policy keeps some cells clear)"
+            ),
+            Self::MinesPerCell { value } => write!(
+                f,
+                "{MINES_PER_CELL_VAR} must be from 1 to {MOST_MINES_PER_CELL}, got {value}"
+            ),
+            Self::TooManyCells {
+                width,
+                height,
+                depth,
+            } => write!(
+                f,
+                "{WIDTH_VAR} x {HEIGHT_VAR} x {DEPTH_VAR} must be at most \
+                 {MOST_CELLS} cells, got {width} x {height} x {depth}"
+            ),
+            Self::NoLives => write!(
+                f,
+                "{LIVES_VAR} must be at least 1: the game ends on the hit that spends the last life"
+            ),
+            Self::Density { value } => write!(
+                f,
+                "{DENSITY_VAR} must be from {} to {} percent, got {value}",
+                DENSITIES.start(),
+                DENSITIES.end()
+            ),
+            Self::UnprotectedNoGuess => write!(
+                f,
+                "{NO_GUESS_VAR} needs a {FIRST_CLICK_VAR} policy that keeps the \
+                 first click safe"
+            ),
+            Self::StackedNoGuess => write!(
+                f,
+                "{NO_GUESS_VAR} needs {MINES_PER_CELL_VAR}=1: the solver that \
+                 checks a no-guess board assumes one mine per cell"
+            ),
+            Self::ShapedNoGuess => write!(
+                f,
+                "{NO_GUESS_VAR} needs one layer, no {MASK_VAR} and {KERNEL_VAR}=moore: \
+                 on other shapes a first click may have no solvable layout"
+            ),
+            Self::NoGuessSize { width, height } => write!(
+                f,
+                "{NO_GUESS_VAR} needs sides of at least {LEAST_NO_GUESS_SIDE} and at \
+                 most {MOST_NO_GUESS_CELLS} cells, got {width} x {height}"
+            ),
+            Self::NoGuessDensity { mines, most } => write!(
+                f,
+                "{MINES_VAR} is {mines} but {NO_GUESS_VAR} allows at most {most} \
+                 on this board, {MOST_NO_GUESS_DENSITY}% of its cells"
             ),
         }
     }
@@ -52,38 +563,344 @@ impl fmt::Display for ConfigError {
 impl Config {
     /// Validates dimensions and mine count, naming the offending variable.
     pub fn new(width: usize, height: usize, mines: usize) -> Result<Self, ConfigError> {
-        if width == 0 {
+        Self {
+            width,
+            height,
+            depth: DEFAULT_DEPTH,
+            mines,
+            mines_per_cell: DEFAULT_MINES_PER_CELL,
+            lives: DEFAULT_LIVES,
+            question_marks: false,
+            generation: Generation::Uniform,
+            first_click: FirstClick::Opening,
+            undo: UndoPolicy::LossIsFinal,
+            topology: Topology::Flat,
+            grid: Grid::Square,
+            kernel: Kernel::Moore,
+            mask: None,
+        }
+        .check()
+    }
+
+    /// Runs [`Config::new`]'s checks again, for a config whose shape has been
+    /// changed since by the `with_*` setters.
+    pub fn check(self) -> Result<Self, ConfigError> {
+        if self.width == 0 {
             return Err(ConfigError::ZeroDimension { var: WIDTH_VAR });
         }
-        if height == 0 {
+        if self.height == 0 {
             return Err(ConfigError::ZeroDimension { var: HEIGHT_VAR });
         }
-        let capacity = (width * height).saturating_sub(SAFE_REGION);
-        match mines > capacity {
-            true => Err(ConfigError::TooManyMines { mines, capacity }),
-            false => Ok(Self {
-                width,
-                height,
-                mines,
+        if self.depth == 0 {
+            return Err(ConfigError::ZeroDimension { var: DEPTH_VAR });
+        }
+        let slots = self
+            .width
+            .checked_mul(self.height)
+            .and_then(|slots| slots.checked_mul(self.depth));
+        if slots.is_none_or(|slots| slots > MOST_CELLS) {
+            return Err(ConfigError::TooManyCells {
+                width: self.width,
+                height: self.height,
+                depth: self.depth,
+            });
+        }
+        if self.lives == 0 {
+            return Err(ConfigError::NoLives);
+        }
+        if !(1..=MOST_MINES_PER_CELL).contains(&self.mines_per_cell) {
+            return Err(ConfigError::MinesPerCell {
+                value: self.mines_per_cell,
+            });
+        }
+        if self.first_click == FirstClick::Unprotected && self.generation == Generation::NoGuess {
+            return Err(ConfigError::UnprotectedNoGuess);
+        }
+        if self.mines_per_cell > 1 && self.generation == Generation::NoGuess {
+            return Err(ConfigError::StackedNoGuess);
+        }
+        if self.generation == Generation::NoGuess {
+            if self.mask.is_some() || self.depth > 1 || self.kernel != Kernel::Moore {
+                return Err(ConfigError::ShapedNoGuess);
+            }
+            let cells = self.area();
+            if self.width.min(self.height) < LEAST_NO_GUESS_SIDE || cells > MOST_NO_GUESS_CELLS {
+                return Err(ConfigError::NoGuessSize {
+                    width: self.width,
+                    height: self.height,
+                });
+            }
+            let most = cells * MOST_NO_GUESS_DENSITY / 100;
+            if self.mines > most {
+                return Err(ConfigError::NoGuessDensity {
+                    mines: self.mines,
+                    most,
+                });
+            }
+        }
+        if let Some(mask) = &self.mask {
+            if (mask.width(), mask.rows()) != (self.width, self.rows()) {
+                return Err(ConfigError::MaskSize {
+                    width: mask.width(),
+                    rows: mask.rows(),
+                    expected_width: self.width,
+                    expected_rows: self.rows(),
+                });
+            }
+        }
+        if self.grid == Grid::Hex && self.topology.wraps_rows() && self.height % 2 == 1 {
+            return Err(ConfigError::UnevenHexTorus {
+                height: self.height,
+            });
+        }
+        if self.grid == Grid::Hex && self.kernel != Kernel::Moore {
+            return Err(ConfigError::HexKernel {
+                kernel: self.kernel,
+            });
+        }
+        let capacity = self.capacity();
+        match self.mines > capacity {
+            true => Err(ConfigError::TooManyMines {
+                mines: self.mines,
+                capacity,
             }),
+            false => Ok(self),
+        }
+    }
+
+    /// Follow with [`Config::check`]: a game needs at least one life.
+    pub fn with_lives(self, lives: usize) -> Self {
+        Self { lives, ..self }
+    }
+
+    pub fn with_question_marks(self, question_marks: bool) -> Self {
+        Self {
+            question_marks,
+            ..self
         }
     }
 
     /// Resolves the board from the build environment, falling back to a 10x10
     /// grid with 15 mines when a variable is unset.
     pub fn from_build_env() -> Result<Self, ConfigError> {
-        let width = parse(WIDTH_VAR, option_env!("MINESWEEPER_WIDTH"), DEFAULT_WIDTH)?;
-        let height = parse(
-            HEIGHT_VAR,
-            option_env!("MINESWEEPER_HEIGHT"),
-            DEFAULT_HEIGHT,
+        Self::from_vars(build_var)
+    }
+
+    /// Resolves the board from any source of the variables in [`VARS`],
+    /// looked up by name, with the same defaults and the same errors as the
+    /// build environment.
+    pub fn from_vars<'a>(get: impl Fn(&str) -> Option<&'a str>) -> Result<Self, ConfigError> {
+        let width = parse(WIDTH_VAR, get(WIDTH_VAR), DEFAULT_WIDTH)?;
+        let height = parse(HEIGHT_VAR, get(HEIGHT_VAR), DEFAULT_HEIGHT)?;
+        let depth = parse(DEPTH_VAR, get(DEPTH_VAR), DEFAULT_DEPTH)?;
+        let mines = parse(MINES_VAR, get(MINES_VAR), DEFAULT_MINES)?;
+        let mines_per_cell = parse(
+            MINES_PER_CELL_VAR,
+            get(MINES_PER_CELL_VAR),
+            DEFAULT_MINES_PER_CELL,
         )?;
-        let mines = parse(MINES_VAR, option_env!("MINESWEEPER_MINES"), DEFAULT_MINES)?;
-        Self::new(width, height, mines)
+        let lives = parse(LIVES_VAR, get(LIVES_VAR), DEFAULT_LIVES)?;
+        let question_marks = parse_flag(QUESTION_MARKS_VAR, get(QUESTION_MARKS_VAR), false)?;
+        let no_guess = parse_flag(NO_GUESS_VAR, get(NO_GUESS_VAR), false)?;
+        let generation = match no_guess {
+            true => Generation::NoGuess,
+            false => Generation::Uniform,
+        };
+        let first_click = match parse_choice(FIRST_CLICK_VAR, get(FIRST_CLICK_VAR), FIRST_CLICKS)? {
+            Some("unprotected") => FirstClick::Unprotected,
+            Some("safe-cell") => FirstClick::SafeCell,
+            Some("relocate") => FirstClick::Relocate,
+            _ => FirstClick::Opening,
+        };
+        let undo = match parse_flag(UNDO_LOSSES_VAR, get(UNDO_LOSSES_VAR), false)? {
+            true => UndoPolicy::AllowAfterLoss,
+            false => UndoPolicy::LossIsFinal,
+        };
+        let topology = match parse_choice(TOPOLOGY_VAR, get(TOPOLOGY_VAR), TOPOLOGIES)? {
+            Some("cylinder") => Topology::Cylinder,
+            Some("torus") => Topology::Torus,
+            _ => Topology::Flat,
+        };
+        let grid = match parse_choice(GRID_VAR, get(GRID_VAR), GRIDS)? {
+            Some("hex") => Grid::Hex,
+            _ => Grid::Square,
+        };
+        let kernel = parse_kernel(get(KERNEL_VAR))?;
+        let mask = parse_mask(get(MASK_VAR))?;
+        // Mines are checked last, by `check`, once the shape that decides how
+        // many fit is known.
+        Self {
+            mines,
+            ..Self::new(width, height, 0)?
+        }
+        .with_question_marks(question_marks)
+        .with_generation(generation)
+        .with_first_click(first_click)
+        .with_undo(undo)
+        .with_topology(topology)
+        .with_grid(grid)
+        .with_kernel(kernel)
+        .with_depth(depth)
+        .with_mask(mask)
+        .with_mines_per_cell(mines_per_cell)
+        .with_lives(lives)
+        .check()
+    }
+
+    pub fn with_generation(self, generation: Generation) -> Self {
+        Self { generation, ..self }
     }
 
+    /// Follow with [`Config::check`]: the policy decides how many cells are
+    /// kept clear, and an unprotected click rules out no-guess generation.
+    pub fn with_first_click(self, first_click: FirstClick) -> Self {
+        Self {
+            first_click,
+            ..self
+        }
+    }
+
+    pub fn with_undo(self, undo: UndoPolicy) -> Self {
+        Self { undo, ..self }
+    }
+
+    /// Follow with [`Config::check`]: a hex torus needs an even height.
+    pub fn with_topology(self, topology: Topology) -> Self {
+        Self { topology, ..self }
+    }
+
+    /// Follow with [`Config::check`]: a hex cell reserves fewer neighbours on
+    /// the first click, so more mines fit.
+    pub fn with_grid(self, grid: Grid) -> Self {
+        Self { grid, ..self }
+    }
+
+    /// Follow with [`Config::check`]: a larger kernel reserves more cells on
+    /// the first click, and a hex grid takes none.
+    pub fn with_kernel(self, kernel: Kernel) -> Self {
+        Self { kernel, ..self }
+    }
+
+    /// Follow with [`Config::check`]: a cube holds more cells, but its first
+    /// click also reserves up to 27 of them.
+    pub fn with_depth(self, depth: usize) -> Self {
+        Self { depth, ..self }
+    }
+
+    /// Follow with [`Config::check`]: stacks multiply the room for mines, but
+    /// rule out no-guess generation.
+    pub fn with_mines_per_cell(self, mines_per_cell: usize) -> Self {
+        Self {
+            mines_per_cell,
+            ..self
+        }
+    }
+
+    /// Follow with [`Config::check`]: the mask must cover every row of every
+    /// layer, and gaps leave less room for mines.
+    pub fn with_mask(self, mask: Option<Mask>) -> Self {
+        Self { mask, ..self }
+    }
+
+    /// Cells that exist, gaps excluded.
     pub fn cells(&self) -> usize {
-        self.width * self.height
+        self.positions().count()
+    }
+
+    /// Every slot of the stored rectangle, gaps included: the length of a
+    /// row-major array indexed `row * width + col`.
+    pub fn area(&self) -> usize {
+        self.width * self.rows()
+    }
+
+    /// Whether `(row, col)` is a cell: inside the rectangle and not a gap.
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        row < self.rows()
+            && col < self.width
+            && self
+                .mask
+                .as_ref()
+                .is_none_or(|mask| mask.contains(row, col))
+    }
+
+    /// Every cell in row-major order, gaps skipped.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.rows())
+            .flat_map(move |row| (0..self.width).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.contains(row, col))
+    }
+
+    /// Rows on the board as stored: every layer's rows, one layer after another.
+    pub fn rows(&self) -> usize {
+        self.height * self.depth
+    }
+
+    /// The most cells the first-click policy can keep clear. An opening keeps
+    /// the click and its neighbours, at whichever cell has the most of them;
+    /// every other policy keeps one cell, the click or wherever a relocated
+    /// mine leaves room, and so does an unprotected board, so it can be won.
+    pub fn safe_region(&self) -> usize {
+        match self.first_click {
+            FirstClick::Opening => geometry::safe_region(self),
+            FirstClick::Unprotected | FirstClick::SafeCell | FirstClick::Relocate => 1,
+        }
+    }
+
+    /// How many mines fit once the largest possible safe region is set aside,
+    /// each remaining cell holding as many as it may.
+    pub fn capacity(&self) -> usize {
+        self.cells().saturating_sub(self.safe_region()) * self.mines_per_cell
+    }
+}
+
+/// The variable a setting stands for when it is named in lower case with
+/// dashes, as the terminal flags and the engine protocol name them:
+/// `first-click` is `MINESWEEPER_FIRST_CLICK`.
+pub fn var_named(name: &str) -> Option<&'static str> {
+    if !name
+        .bytes()
+        .all(|byte| byte.is_ascii_lowercase() || byte == b'-')
+    {
+        return None;
+    }
+    let var = format!("MINESWEEPER_{}", name.to_uppercase().replace('-', "_"));
+    VARS.iter().find(|&&known| known == var).copied()
+}
+
+/// `option_env!` takes a literal, so each name is spelled out once more.
+fn build_var(var: &str) -> Option<&'static str> {
+    match var {
+        WIDTH_VAR => option_env!("MINESWEEPER_WIDTH"),
+        HEIGHT_VAR => option_env!("MINESWEEPER_HEIGHT"),
+        DEPTH_VAR => option_env!("MINESWEEPER_DEPTH"),
+        MINES_VAR => option_env!("MINESWEEPER_MINES"),
+        FIRST_CLICK_VAR => option_env!("MINESWEEPER_FIRST_CLICK"),
+        MINES_PER_CELL_VAR => option_env!("MINESWEEPER_MINES_PER_CELL"),
+        LIVES_VAR => option_env!("MINESWEEPER_LIVES"),
+        QUESTION_MARKS_VAR => option_env!("MINESWEEPER_QUESTION_MARKS"),
+        NO_GUESS_VAR => option_env!("MINESWEEPER_NO_GUESS"),
+        UNDO_LOSSES_VAR => option_env!("MINESWEEPER_UNDO_LOSSES"),
+        TOPOLOGY_VAR => option_env!("MINESWEEPER_TOPOLOGY"),
+        GRID_VAR => option_env!("MINESWEEPER_GRID"),
+        KERNEL_VAR => option_env!("MINESWEEPER_KERNEL"),
+        MASK_VAR => option_env!("MINESWEEPER_MASK"),
+        _ => None,
+    }
+}
+
+/// The endless board's mine density, in percent.
+pub fn density_from_build_env() -> Result<usize, ConfigError> {
+    check_density(parse(
+        DENSITY_VAR,
+        option_env!("MINESWEEPER_DENSITY"),
+        DEFAULT_DENSITY,
+    )?)
+}
+
+pub fn check_density(value: usize) -> Result<usize, ConfigError> {
+    match DENSITIES.contains(&value) {
+        true => Ok(value),
+        false => Err(ConfigError::Density { value }),
     }
 }
 
@@ -97,6 +914,60 @@ fn parse(var: &'static str, raw: Option<&str>, fallback: usize) -> Result<usize,
     })
 }
 
+fn parse_flag(var: &'static str, raw: Option<&str>, fallback: bool) -> Result<bool, ConfigError> {
+    let Some(value) = raw else {
+        return Ok(fallback);
+    };
+    match value.trim() {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(ConfigError::NotABool {
+            var,
+            value: value.to_owned(),
+        }),
+    }
+}
+
+/// `None` when unset; otherwise the matching entry of `options`.
+fn parse_choice(
+    var: &'static str,
+    raw: Option<&str>,
+    options: &'static [&'static str],
+) -> Result<Option<&'static str>, ConfigError> {
+    let Some(value) = raw else {
+        return Ok(None);
+    };
+    match options.iter().find(|&&option| option == value.trim()) {
+        Some(&option) => Ok(Some(option)),
+        None => Err(ConfigError::NotOneOf {
+            var,
+            value: value.to_owned(),
+            options,
+        }),
+    }
+}
+
+fn parse_mask(raw: Option<&str>) -> Result<Option<Mask>, ConfigError> {
+    let Some(value) = raw else {
+        return Ok(None);
+    };
+    match Mask::parse(value) {
+        Some(mask) => Ok(Some(mask)),
+        None => Err(ConfigError::NotAMask {
+            value: value.to_owned(),
+        }),
+    }
+}
+
+fn parse_kernel(raw: Option<&str>) -> Result<Kernel, ConfigError> {
+    let Some(value) = raw else {
+        return Ok(Kernel::Moore);
+    };
+    Kernel::parse(value).ok_or_else(|| ConfigError::NotAKernel {
+        value: value.to_owned(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,7 +980,7 @@ mod tests {
     #[test]
     fn the_build_environment_always_resolves_to_a_playable_board() {
         let config = Config::from_build_env().expect("build variables describe a valid board");
-        assert!(config.mines + SAFE_REGION <= config.cells());
+        assert!(config.mines <= config.capacity());
     }
 
     #[test]
@@ -130,6 +1001,22 @@ mod tests {
         assert!(format!("{}", error.unwrap_err()).contains(MINES_VAR));
     }
 
+    #[test]
+    fn a_boolean_build_variable_accepts_words_or_digits_and_nothing_else() {
+        assert_eq!(
+            parse_flag(QUESTION_MARKS_VAR, Some("true"), false),
+            Ok(true)
+        );
+        assert_eq!(parse_flag(QUESTION_MARKS_VAR, Some(" 0 "), true), Ok(false));
+        assert_eq!(
+            parse_flag(QUESTION_MARKS_VAR, Some("yes"), false),
+            Err(ConfigError::NotABool {
+                var: QUESTION_MARKS_VAR,
+                value: "yes".to_owned(),
+            })
+        );
+    }
+
     #[test]
     fn a_zero_dimension_is_rejected_naming_the_offending_axis() {
         assert_eq!(
@@ -142,6 +1029,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn a_board_past_the_cell_limit_is_refused_before_anything_walks_it() {
+        assert!(Config::new(256, 256, 10).is_ok());
+        assert_eq!(
+            Config::new(257, 256, 10),
+            Err(ConfigError::TooManyCells {
+                width: 257,
+                height: 256,
+                depth: 1,
+            })
+        );
+        let huge = Config::new(16, 16, 10)
+            .expect("valid config")
+            .with_depth(usize::MAX / 2);
+        assert!(matches!(
+            huge.check(),
+            Err(ConfigError::TooManyCells { .. })
+        ));
+    }
+
     #[test]
     fn mine_count_may_not_eat_into_the_guaranteed_safe_region() {
         assert_eq!(
@@ -149,7 +1056,18 @@ mod tests {
             Ok(Config {
                 width: 4,
                 height: 4,
+                depth: 1,
                 mines: 7,
+                mines_per_cell: 1,
+                lives: 1,
+                question_marks: false,
+                generation: Generation::Uniform,
+                first_click: FirstClick::Opening,
+                undo: UndoPolicy::LossIsFinal,
+                topology: Topology::Flat,
+                grid: Grid::Square,
+                kernel: Kernel::Moore,
+                mask: None,
             })
         );
         assert_eq!(
@@ -166,4 +1084,251 @@ mod tests {
         assert!(Config::new(2, 2, 0).is_ok());
         assert!(Config::new(2, 2, 1).is_err());
     }
+
+    #[test]
+    fn a_single_row_only_reserves_the_click_and_its_two_neighbours() {
+        let config = Config::new(10, 1, 7).expect("seven mines fit");
+        assert_eq!(config.safe_region(), 3);
+        assert_eq!(config.with_topology(Topology::Torus).capacity(), 7);
+        assert!(Config::new(10, 1, 8).is_err());
+    }
+
+    #[test]
+    fn a_hex_board_fits_more_mines_and_a_hex_torus_needs_an_even_height() {
+        let config = Config::new(9, 9, 72).expect("valid config");
+        assert_eq!(config.clone().with_grid(Grid::Hex).capacity(), 74);
+        let torus = config.with_grid(Grid::Hex).with_topology(Topology::Torus);
+        assert_eq!(
+            torus.check(),
+            Err(ConfigError::UnevenHexTorus { height: 9 })
+        );
+        assert!(Config::new(9, 8, 10)
+            .expect("valid config")
+            .with_grid(Grid::Hex)
+            .with_topology(Topology::Torus)
+            .check()
+            .is_ok());
+    }
+
+    #[test]
+    fn a_cube_reserves_a_three_by_three_by_three_block() {
+        let cube = Config::new(5, 5, 0).expect("valid config").with_depth(5);
+        assert_eq!(cube.cells(), 125);
+        assert_eq!(cube.rows(), 25);
+        assert_eq!(cube.safe_region(), 27);
+        assert_eq!(cube.capacity(), 98);
+        assert_eq!(
+            cube.with_depth(0).check(),
+            Err(ConfigError::ZeroDimension { var: DEPTH_VAR })
+        );
+    }
+
+    #[test]
+    fn a_topology_is_one_of_the_named_options() {
+        assert_eq!(
+            parse_choice(TOPOLOGY_VAR, Some(" torus "), TOPOLOGIES),
+            Ok(Some("torus"))
+        );
+        assert_eq!(parse_choice(TOPOLOGY_VAR, None, TOPOLOGIES), Ok(None));
+        let error = parse_choice(TOPOLOGY_VAR, Some("sphere"), TOPOLOGIES).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "MINESWEEPER_TOPOLOGY must be one of flat, cylinder, torus, got \"sphere\""
+        );
+    }
+
+    #[test]
+    fn a_kernel_is_named_or_spelled_out_as_offsets() {
+        assert_eq!(parse_kernel(None), Ok(Kernel::Moore));
+        assert_eq!(parse_kernel(Some(" knight ")), Ok(Kernel::Knight));
+        let custom = parse_kernel(Some("1,0  -1,2")).expect("two offsets");
+        assert_eq!(custom.to_string(), "-1,2 1,0");
+        assert_eq!(Kernel::parse(&custom.to_string()), Some(custom));
+        for bad in ["", "king", "0,0", "5,0", "1,x"] {
+            assert_eq!(
+                parse_kernel(Some(bad)),
+                Err(ConfigError::NotAKernel {
+                    value: bad.to_owned()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn the_kernel_decides_how_many_cells_the_first_click_reserves() {
+        let config = Config::new(9, 9, 0).expect("valid config");
+        assert_eq!(
+            config.clone().with_kernel(Kernel::Orthogonal).safe_region(),
+            5
+        );
+        assert_eq!(config.clone().with_kernel(Kernel::Knight).safe_region(), 9);
+        assert_eq!(
+            config.clone().with_kernel(Kernel::Radius2).safe_region(),
+            25
+        );
+        assert_eq!(
+            config
+                .with_grid(Grid::Hex)
+                .with_kernel(Kernel::Knight)
+                .check(),
+            Err(ConfigError::HexKernel {
+                kernel: Kernel::Knight
+            })
+        );
+    }
+
+    #[test]
+    fn gaps_in_a_mask_hold_no_mines() {
+        let ring = Mask::from_rows(&["####", "#--#", "####"]).expect("valid mask");
+        assert_eq!(ring.to_string(), "#### #--# ####");
+        assert_eq!(Mask::parse(&ring.to_string()), Some(ring.clone()));
+        let config = Config::new(4, 3, 0)
+            .expect("valid config")
+            .with_mask(Some(ring));
+        assert_eq!(config.area(), 12);
+        assert_eq!(config.cells(), 10);
+        assert!(!config.contains(1, 1));
+        assert_eq!(config.safe_region(), 5);
+        assert_eq!(config.capacity(), 5);
+        assert_eq!(
+            config.clone().with_depth(2).check(),
+            Err(ConfigError::MaskSize {
+                width: 4,
+                rows: 3,
+                expected_width: 4,
+                expected_rows: 6,
+            })
+        );
+    }
+
+    #[test]
+    fn a_mask_must_be_rectangular_and_hold_at_least_one_cell() {
+        assert!(parse_mask(None).expect("unset").is_none());
+        for bad in ["## #", "#.#", "--- ---", ""] {
+            assert_eq!(
+                parse_mask(Some(bad)),
+                Err(ConfigError::NotAMask {
+                    value: bad.to_owned()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn stacked_mines_multiply_the_room_and_rule_out_no_guess() {
+        let config = Config::new(4, 4, 7).expect("valid config");
+        let stacked = config.clone().with_mines_per_cell(3);
+        assert_eq!(stacked.capacity(), 21);
+        assert!(Config {
+            mines: 21,
+            ..stacked.clone()
+        }
+        .check()
+        .is_ok());
+        assert_eq!(
+            stacked.with_generation(Generation::NoGuess).check(),
+            Err(ConfigError::StackedNoGuess)
+        );
+        for value in [0, 10] {
+            assert_eq!(
+                config.clone().with_mines_per_cell(value).check(),
+                Err(ConfigError::MinesPerCell { value })
+            );
+        }
+    }
+
+    #[test]
+    fn no_guess_is_refused_where_some_first_click_has_no_clearable_layout() {
+        let no_guess = |width, height, mines| {
+            Config::new(width, height, mines)
+                .expect("valid config")
+                .with_generation(Generation::NoGuess)
+        };
+        assert!(no_guess(9, 9, 17).check().is_ok());
+        assert!(no_guess(16, 16, 40).check().is_ok());
+        assert!(no_guess(30, 16, 99).check().is_ok());
+        assert_eq!(
+            no_guess(9, 9, 35).check(),
+            Err(ConfigError::NoGuessDensity {
+                mines: 35,
+                most: 17
+            })
+        );
+        assert_eq!(
+            no_guess(30, 16, 130).check(),
+            Err(ConfigError::NoGuessDensity {
+                mines: 130,
+                most: 100
+            })
+        );
+        for (width, height) in [(3, 9), (9, 3), (40, 40)] {
+            assert_eq!(
+                no_guess(width, height, 1).check(),
+                Err(ConfigError::NoGuessSize { width, height })
+            );
+        }
+        let ring = Mask::from_rows(&["######", "#----#", "######"]).expect("valid mask");
+        for shaped in [
+            no_guess(9, 9, 10).with_kernel(Kernel::Knight),
+            no_guess(9, 9, 10).with_depth(2),
+            no_guess(6, 3, 1).with_mask(Some(ring)),
+        ] {
+            assert_eq!(shaped.check(), Err(ConfigError::ShapedNoGuess));
+        }
+    }
+
+    #[test]
+    fn a_game_needs_at_least_one_life() {
+        let config = Config::new(4, 4, 7).expect("valid config");
+        assert_eq!(config.lives, 1);
+        assert_eq!(config.clone().with_lives(3).check().map(|c| c.lives), Ok(3));
+        assert_eq!(config.with_lives(0).check(), Err(ConfigError::NoLives));
+    }
+
+    #[test]
+    fn a_setting_named_in_lower_case_finds_its_variable() {
+        assert_eq!(var_named("width"), Some(WIDTH_VAR));
+        assert_eq!(var_named("first-click"), Some(FIRST_CLICK_VAR));
+        assert_eq!(var_named("WIDTH"), None);
+        assert_eq!(var_named("first_click"), None);
+        assert_eq!(var_named("colour"), None);
+        assert_eq!(var_named("density"), None);
+        assert!(!VARS.contains(&DENSITY_VAR));
+    }
+
+    #[test]
+    fn an_endless_density_must_leave_blanks_finite_and_cells_to_clear() {
+        assert_eq!(check_density(DEFAULT_DENSITY), Ok(DEFAULT_DENSITY));
+        assert_eq!(check_density(5), Err(ConfigError::Density { value: 5 }));
+        assert_eq!(check_density(90), Err(ConfigError::Density { value: 90 }));
+        assert!(density_from_build_env().is_ok());
+    }
+
+    #[test]
+    fn only_an_opening_reserves_the_neighbours_of_the_first_click() {
+        let config = Config::new(4, 4, 7).expect("valid config");
+        assert_eq!(config.safe_region(), 9);
+        for policy in [
+            FirstClick::Unprotected,
+            FirstClick::SafeCell,
+            FirstClick::Relocate,
+        ] {
+            let config = config.clone().with_first_click(policy);
+            assert_eq!(config.safe_region(), 1);
+            assert_eq!(config.capacity(), 15);
+            assert!(Config {
+                mines: 15,
+                ..config
+            }
+            .check()
+            .is_ok());
+        }
+        assert_eq!(
+            config
+                .with_first_click(FirstClick::Unprotected)
+                .with_generation(Generation::NoGuess)
+                .check(),
+            Err(ConfigError::UnprotectedNoGuess)
+        );
+    }
 }
@@ -0,0 +1,219 @@
+//! The daily challenge: one board per UTC day, the same for everyone.
+//!
+//! [`challenge`] derives a [`ShareCode`] from nothing but the date, so players
+//! who never compare notes still open the same mines. The day's number seeds
+//! a version 1 [`Generator`], which picks the difficulty and then the layout
+//! seed, and the board is drawn with version 1 as well: a newer generator
+//! never changes a past day's board.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::game::GameResult;
+use crate::generator::{Generator, Version};
+use crate::share::ShareCode;
+
+const MILLIS_PER_DAY: u64 = 86_400_000;
+
+/// Days from 0000-03-01 to 1970-01-01 in the proleptic Gregorian calendar.
+const EPOCH_SHIFT: u128 = 719_468;
+const DAYS_PER_ERA: u128 = 146_097;
+
+/// Width, height and mines of the classic beginner, intermediate and expert
+/// boards, one of which is drawn each day.
+const PRESETS: [(usize, usize, usize); 3] = [(9, 9, 10), (16, 16, 40), (30, 16, 99)];
+
+/// A UTC calendar day, counted from 1970-01-01.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    days: u64,
+}
+
+impl Date {
+    pub fn from_days(days: u64) -> Self {
+        Self { days }
+    }
+
+    /// The day a Unix timestamp falls on.
+    pub fn from_unix_millis(millis: u64) -> Self {
+        Self::from_days(millis / MILLIS_PER_DAY)
+    }
+
+    /// `None` for a date that does not exist, comes before 1970, or lies so
+    /// far ahead that its day number overflows a `u64`.
+    pub fn from_ymd(year: u64, month: u64, day: u64) -> Option<Self> {
+        if !(1..=12).contains(&month) || day == 0 {
+            return None;
+        }
+        // Howard Hinnant's `days_from_civil`, on years that start in March so
+        // the leap day falls at the end. Worked in `u128`, where no year or
+        // day a `u64` holds can overflow, and narrowed once at the end.
+        let march_year = u128::from(year).checked_sub(u128::from(month <= 2))?;
+        let (era, year_of_era) = (march_year / 400, march_year % 400);
+        let month_from_march = u128::from((month + 9) % 12);
+        let day_of_year = (153 * month_from_march + 2) / 5 + u128::from(day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = (era * DAYS_PER_ERA + day_of_era).checked_sub(EPOCH_SHIFT)?;
+        let date = Self::from_days(u64::try_from(days).ok()?);
+        // An overlong day, like the 30th of February, runs into the next month.
+        (date.ymd() == (year, month, day)).then_some(date)
+    }
+
+    /// Year, month and day, the inverse of [`Date::from_ymd`]. Total: even
+    /// the last day a `u64` counts falls in a year a `u64` holds.
+    pub fn ymd(self) -> (u64, u64, u64) {
+        let days = u128::from(self.days) + EPOCH_SHIFT;
+        let (era, day_of_era) = (days / DAYS_PER_ERA, days % DAYS_PER_ERA);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+        let month = (month_from_march + 2) % 12 + 1;
+        let year = era * 400 + year_of_era + u128::from(month <= 2);
+        // At most `u64::MAX / 365` or so; the day and month are tiny.
+        (year as u64, month as u64, day as u64)
+    }
+}
+
+/// ISO 8601, `2026-10-18`.
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{year:04}-{month:02}-{day:02}")
+    }
+}
+
+impl FromStr for Date {
+    type Err = ();
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts = text.splitn(3, '-').map(str::parse::<u64>);
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) => {
+                Self::from_ymd(year, month, day).ok_or(())
+            }
+            _ => Err(()),
+        }
+    }
+}
+
+/// The board everyone plays on `date`.
+pub fn challenge(date: Date) -> ShareCode {
+    let mut generator = Generator::with_version(Version::V1, date.days);
+    let (width, height, mines) = PRESETS[generator.below(PRESETS.len() as u64) as usize];
+    ShareCode {
+        config: Config::new(width, height, mines).expect("the presets are valid boards"),
+        seed: generator.next_u64(),
+        generator: Version::V1,
+    }
+}
+
+/// How the player's daily game ended, kept between visits so the page can
+/// tell them today's board is already done.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Completion {
+    pub date: Date,
+    pub result: GameResult,
+}
+
+impl Completion {
+    pub fn is_for(&self, date: Date) -> bool {
+        self.date == date
+    }
+}
+
+/// `2026-10-18 won`, one line.
+impl fmt::Display for Completion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = match self.result {
+            GameResult::Won => "won",
+            GameResult::Lost => "lost",
+        };
+        write!(f, "{} {result}", self.date)
+    }
+}
+
+impl FromStr for Completion {
+    type Err = ();
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let (date, result) = text.trim().split_once(' ').ok_or(())?;
+        let result = match result {
+            "won" => GameResult::Won,
+            "lost" => GameResult::Lost,
+            _ => return Err(()),
+        };
+        Ok(Self {
+            date: date.parse()?,
+            result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_convert_to_and_from_the_calendar() {
+        assert_eq!(Date::from_days(0).to_string(), "1970-01-01");
+        assert_eq!(Date::from_ymd(2000, 2, 29), Some(Date::from_days(11_016)));
+        assert_eq!("2026-10-18".parse(), Ok(Date::from_days(20_744)));
+        assert_eq!(
+            Date::from_unix_millis(20_744 * MILLIS_PER_DAY + MILLIS_PER_DAY - 1),
+            Date::from_days(20_744)
+        );
+        for days in 0..50_000 {
+            let date = Date::from_days(days);
+            assert_eq!(date.to_string().parse(), Ok(date));
+        }
+        assert_eq!("2026-02-29".parse::<Date>(), Err(()));
+        assert_eq!("1900-02-28".parse::<Date>(), Err(()));
+        assert_eq!("2026-13-01".parse::<Date>(), Err(()));
+    }
+
+    #[test]
+    fn a_year_too_far_ahead_to_count_is_refused_and_the_last_day_round_trips() {
+        assert_eq!(Date::from_ymd(u64::MAX, 12, 31), None);
+        assert_eq!(Date::from_ymd(2026, 10, u64::MAX), None);
+        assert_eq!(format!("{}-01-01", u64::MAX).parse::<Date>(), Err(()));
+        let last = Date::from_days(u64::MAX);
+        let (year, month, day) = last.ymd();
+        assert_eq!(Date::from_ymd(year, month, day), Some(last));
+        assert_eq!(last.to_string().parse(), Ok(last));
+        assert_eq!(Date::from_ymd(year + 1, 1, 1), None);
+    }
+
+    #[test]
+    fn everyone_gets_the_same_board_and_it_changes_at_midnight() {
+        let today: Date = "2026-10-18".parse().expect("valid date");
+        assert_eq!(challenge(today), challenge(today));
+        assert_ne!(challenge(today), challenge(Date::from_days(20_745)));
+        let code = challenge(today);
+        assert_eq!(code.generator, Version::V1);
+        assert_eq!(
+            (code.config.width, code.config.height, code.config.mines),
+            GOLDEN_PRESET
+        );
+        assert_eq!(code.seed, GOLDEN_SEED);
+    }
+
+    #[test]
+    fn a_completion_reads_back_and_names_its_day() {
+        let today: Date = "2026-10-18".parse().expect("valid date");
+        let done = Completion {
+            date: today,
+            result: GameResult::Lost,
+        };
+        assert_eq!(done.to_string(), "2026-10-18 lost");
+        assert_eq!(done.to_string().parse(), Ok(done));
+        assert!(done.is_for(today));
+        assert!(!done.is_for(Date::from_days(20_745)));
+        assert_eq!("2026-10-18 drawn".parse::<Completion>(), Err(()));
+    }
+
+    const GOLDEN_PRESET: (usize, usize, usize) = (30, 16, 99);
+    const GOLDEN_SEED: u64 = 15096623605185298318;
+}
@@ -0,0 +1,217 @@
+//! Pieces shared by the plain-text formats in [`replay`](crate::replay) and
+//! [`save`](crate::save): a `name version` header, one `key value` line per
+//! setting, and validation of the settings through [`Config::new`].
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::config::{
+    Config, ConfigError, FirstClick, Generation, Grid, Kernel, Mask, Topology, UndoPolicy,
+};
+
+/// Why the first line did not open a document of the expected kind.
+pub(crate) enum HeaderError {
+    Missing,
+    Unsupported(String),
+}
+
+/// Why the `key value` settings do not describe a board.
+pub(crate) enum SettingsError {
+    Missing(&'static str),
+    Invalid(ConfigError),
+}
+
+/// Non-blank lines, trimmed and numbered from 1 as an editor would show them.
+pub(crate) fn numbered_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+}
+
+/// Reads the `name version` line, accepting any version in `versions`, and
+/// returns the version found.
+pub(crate) fn expect_header<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    name: &str,
+    versions: RangeInclusive<u32>,
+) -> Result<u32, HeaderError> {
+    match lines.next().and_then(|(_, line)| line.split_once(' ')) {
+        Some((found, number)) if found == name => number
+            .parse()
+            .ok()
+            .filter(|version| versions.contains(version))
+            .ok_or_else(|| HeaderError::Unsupported(number.to_owned())),
+        _ => Err(HeaderError::Missing),
+    }
+}
+
+pub(crate) fn write_settings(f: &mut fmt::Formatter<'_>, config: &Config) -> fmt::Result {
+    writeln!(f, "width {}", config.width)?;
+    writeln!(f, "height {}", config.height)?;
+    writeln!(f, "depth {}", config.depth)?;
+    writeln!(f, "mines {}", config.mines)?;
+    writeln!(f, "mines-per-cell {}", config.mines_per_cell)?;
+    writeln!(f, "lives {}", config.lives)?;
+    writeln!(f, "question-marks {}", config.question_marks)?;
+    writeln!(f, "generation {}", generation_name(config.generation))?;
+    writeln!(f, "first-click {}", first_click_name(config.first_click))?;
+    writeln!(f, "undo {}", undo_name(config.undo))?;
+    writeln!(f, "topology {}", topology_name(config.topology))?;
+    writeln!(f, "grid {}", grid_name(config.grid))?;
+    writeln!(f, "kernel {}", config.kernel)?;
+    // Only a shaped board has a mask line, so a rectangle's file reads as before.
+    match &config.mask {
+        Some(mask) => writeln!(f, "mask {mask}"),
+        None => Ok(()),
+    }
+}
+
+/// The settings lines, each optional until [`Settings::config`] checks them.
+#[derive(Default)]
+pub(crate) struct Settings {
+    width: Option<usize>,
+    height: Option<usize>,
+    depth: Option<usize>,
+    mines: Option<usize>,
+    mines_per_cell: Option<usize>,
+    lives: Option<usize>,
+    question_marks: Option<bool>,
+    generation: Option<Generation>,
+    first_click: Option<FirstClick>,
+    undo: Option<UndoPolicy>,
+    topology: Option<Topology>,
+    grid: Option<Grid>,
+    kernel: Option<Kernel>,
+    mask: Option<Mask>,
+}
+
+impl Settings {
+    /// `None` for an unknown key or an unreadable value.
+    pub(crate) fn set(&mut self, key: &str, value: &str) -> Option<()> {
+        match key {
+            "width" => self.width = Some(value.parse().ok()?),
+            "height" => self.height = Some(value.parse().ok()?),
+            "depth" => self.depth = Some(value.parse().ok()?),
+            "mines" => self.mines = Some(value.parse().ok()?),
+            "mines-per-cell" => self.mines_per_cell = Some(value.parse().ok()?),
+            "lives" => self.lives = Some(value.parse().ok()?),
+            "question-marks" => self.question_marks = Some(value.parse().ok()?),
+            "generation" => self.generation = Some(parse_generation(value)?),
+            "first-click" => self.first_click = Some(parse_first_click(value)?),
+            "undo" => self.undo = Some(parse_undo(value)?),
+            "topology" => self.topology = Some(parse_topology(value)?),
+            "grid" => self.grid = Some(parse_grid(value)?),
+            "kernel" => self.kernel = Some(Kernel::parse(value)?),
+            "mask" => self.mask = Some(Mask::parse(value)?),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// Validated exactly as a build-time config is, so a hand-edited file
+    /// cannot describe a board the game would refuse to start.
+    pub(crate) fn config(&self) -> Result<Config, SettingsError> {
+        let width = self.width.ok_or(SettingsError::Missing("width"))?;
+        let height = self.height.ok_or(SettingsError::Missing("height"))?;
+        let mines = self.mines.ok_or(SettingsError::Missing("mines"))?;
+        // Mines are checked last, once the shape that decides how many fit is
+        // known.
+        let config = Config::new(width, height, 0).map_err(SettingsError::Invalid)?;
+        Config { mines, ..config }
+            .with_question_marks(self.question_marks.unwrap_or_default())
+            .with_generation(self.generation.unwrap_or_default())
+            .with_first_click(self.first_click.unwrap_or_default())
+            .with_undo(self.undo.unwrap_or_default())
+            .with_topology(self.topology.unwrap_or_default())
+            .with_grid(self.grid.unwrap_or_default())
+            .with_kernel(self.kernel.unwrap_or_default())
+            .with_depth(self.depth.unwrap_or(1))
+            .with_mask(self.mask.clone())
+            .with_mines_per_cell(self.mines_per_cell.unwrap_or(1))
+            .with_lives(self.lives.unwrap_or(1))
+            .check()
+            .map_err(SettingsError::Invalid)
+    }
+}
+
+fn generation_name(generation: Generation) -> &'static str {
+    match generation {
+        Generation::Uniform => "uniform",
+        Generation::NoGuess => "no-guess",
+    }
+}
+
+fn parse_generation(text: &str) -> Option<Generation> {
+    match text {
+        "uniform" => Some(Generation::Uniform),
+        "no-guess" => Some(Generation::NoGuess),
+        _ => None,
+    }
+}
+
+fn first_click_name(first_click: FirstClick) -> &'static str {
+    match first_click {
+        FirstClick::Unprotected => "unprotected",
+        FirstClick::SafeCell => "safe-cell",
+        FirstClick::Opening => "opening",
+        FirstClick::Relocate => "relocate",
+    }
+}
+
+fn parse_first_click(text: &str) -> Option<FirstClick> {
+    match text {
+        "unprotected" => Some(FirstClick::Unprotected),
+        "safe-cell" => Some(FirstClick::SafeCell),
+        "opening" => Some(FirstClick::Opening),
+        "relocate" => Some(FirstClick::Relocate),
+        _ => None,
+    }
+}
+
+fn undo_name(undo: UndoPolicy) -> &'static str {
+    match undo {
+        UndoPolicy::LossIsFinal => "loss-is-final",
+        UndoPolicy::AllowAfterLoss => "allow-after-loss",
+    }
+}
+
+fn parse_undo(text: &str) -> Option<UndoPolicy> {
+    match text {
+        "loss-is-final" => Some(UndoPolicy::LossIsFinal),
+        "allow-after-loss" => Some(UndoPolicy::AllowAfterLoss),
+        _ => None,
+    }
+}
+
+fn topology_name(topology: Topology) -> &'static str {
+    match topology {
+        Topology::Flat => "flat",
+        Topology::Cylinder => "cylinder",
+        Topology::Torus => "torus",
+    }
+}
+
+fn parse_topology(text: &str) -> Option<Topology> {
+    match text {
+        "flat" => Some(Topology::Flat),
+        "cylinder" => Some(Topology::Cylinder),
+        "torus" => Some(Topology::Torus),
+        _ => None,
+    }
+}
+
+fn grid_name(grid: Grid) -> &'static str {
+    match grid {
+        Grid::Square => "square",
+        Grid::Hex => "hex",
+    }
+}
+
+fn parse_grid(text: &str) -> Option<Grid> {
+    match text {
+        "square" => Some(Grid::Square),
+        "hex" => Some(Grid::Hex),
+        _ => None,
+    }
+}
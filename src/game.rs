@@ -4,16 +4,40 @@
 //! module builds and runs on the host toolchain, which is what makes the rules
 //! testable with a plain `cargo test`.
 
-use rand::seq::SliceRandom;
-use rand::Rng;
+use std::fmt;
 
-use crate::config::Config;
+use crate::config::{Config, FirstClick, Generation};
+use crate::generator::{Generator, Version};
+use crate::geometry;
+use crate::solver;
+
+/// Draws a no-guess board may take before its first click gives up. High
+/// densities can make a solvable layout rare, and the click runs on the page's
+/// only thread, so a search past this fails rather than hangs.
+pub const NO_GUESS_ATTEMPTS: usize = 1000;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Cell {
-    Mine,
-    /// Number of mines touching this cell; `Adjacent(0)` is a blank cell.
-    Adjacent(u8),
+pub(crate) enum Cell {
+    /// How many mines the cell holds: always 1 unless the config stacks them.
+    Mine(u8),
+    /// Number of mines touching this cell, every mine of a stack included;
+    /// `Adjacent(0)` is a blank cell. Wide enough for full stacks all round
+    /// the largest kernel.
+    Adjacent(u16),
+}
+
+impl Cell {
+    pub fn is_mine(self) -> bool {
+        matches!(self, Cell::Mine(_))
+    }
+
+    /// Mines held in this cell.
+    pub fn mines(self) -> usize {
+        match self {
+            Cell::Mine(count) => count as usize,
+            Cell::Adjacent(_) => 0,
+        }
+    }
 }
 
 impl Default for Cell {
@@ -22,11 +46,135 @@ impl Default for Cell {
     }
 }
 
+/// What the player has written on a covered cell.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub struct CellState {
+pub(crate) enum Mark {
+    #[default]
+    None,
+    /// Claims this many mines: always 1 unless the config stacks them.
+    Flag(u8),
+    /// "Suspicious but unsure". Purely a note: it neither counts against the
+    /// mine total nor protects the cell from a reveal.
+    Question,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct CellState {
     pub content: Cell,
     pub uncovered: bool,
-    pub flagged: bool,
+    pub mark: Mark,
+}
+
+impl CellState {
+    pub fn is_flagged(&self) -> bool {
+        matches!(self.mark, Mark::Flag(_))
+    }
+
+    /// Mines the player's flag claims.
+    pub fn flags(&self) -> usize {
+        match self.mark {
+            Mark::Flag(count) => count as usize,
+            Mark::None | Mark::Question => 0,
+        }
+    }
+
+    /// A mine set off in play, which stays uncovered and known.
+    pub fn is_exploded(&self) -> bool {
+        self.uncovered && self.content.is_mine()
+    }
+
+    /// Mines the player has accounted for here: what a flag claims, or every
+    /// mine of an exploded cell, which counts as if flagged.
+    pub fn known_mines(&self) -> usize {
+        match self.is_exploded() {
+            true => self.content.mines(),
+            false => self.flags(),
+        }
+    }
+}
+
+/// Prints what the player sees, so a debug dump in a log or a failed
+/// assertion gives away no more than the board on screen.
+impl fmt::Debug for CellState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        PlayerView::of(self).fmt(f)
+    }
+}
+
+/// What a player can see of one cell, and all that the views, the solvers
+/// and the engine protocol are given: a covered cell says nothing about what
+/// it holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerView {
+    /// A gap in a masked board, which is no cell at all.
+    Gap,
+    Covered,
+    /// Covered, with a question mark on it.
+    Questioned,
+    /// Covered, with a flag claiming this many mines.
+    Flagged(u8),
+    /// Uncovered and safe, with the mines around it.
+    Number(u16),
+    /// An uncovered mine, set off in play or shown by the loss, with the
+    /// number of mines it holds.
+    Mine(u8),
+}
+
+impl PlayerView {
+    fn of(cell: &CellState) -> Self {
+        match (cell.uncovered, cell.mark, cell.content) {
+            (false, Mark::None, _) => PlayerView::Covered,
+            (false, Mark::Question, _) => PlayerView::Questioned,
+            (false, Mark::Flag(count), _) => PlayerView::Flagged(count),
+            (true, _, Cell::Adjacent(count)) => PlayerView::Number(count),
+            (true, _, Cell::Mine(count)) => PlayerView::Mine(count),
+        }
+    }
+
+    /// Still to be uncovered, marked or not. A gap is not.
+    pub fn is_covered(self) -> bool {
+        matches!(
+            self,
+            PlayerView::Covered | PlayerView::Questioned | PlayerView::Flagged(_)
+        )
+    }
+
+    /// Mines in plain sight: those of an uncovered mine, otherwise none.
+    pub fn mines(self) -> usize {
+        match self {
+            PlayerView::Mine(count) => usize::from(count),
+            _ => 0,
+        }
+    }
+}
+
+/// The board as the player sees it: its config and one [`PlayerView`] per
+/// cell, taken from [`Board::view_rows`] and nothing else. The solver and the
+/// probability engine take one rather than a [`Board`], which could be cloned
+/// and revealed on to find out what a covered cell holds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlayerBoard {
+    config: Config,
+    views: Vec<PlayerView>,
+}
+
+impl PlayerBoard {
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The cell at `(row, col)`, or a gap off the board.
+    pub fn view(&self, row: usize, col: usize) -> PlayerView {
+        match self.config.contains(row, col) {
+            true => self.views[row * self.config.width + col],
+            false => PlayerView::Gap,
+        }
+    }
+
+    /// The distinct cells touching this one; see [`Board::neighbors`].
+    pub fn neighbors(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> {
+        geometry::neighbors(&self.config, row, col)
+    }
 }
 
 /// Terminal state of a game. A reveal that ends nothing returns `None`, so
@@ -42,58 +190,150 @@ pub enum GameResult {
 pub enum Action {
     Reveal(usize, usize),
     Flag(usize, usize),
+    /// Reveal every unflagged neighbour of a number whose flags are all placed.
+    Chord(usize, usize),
+    /// Step back or forward through [`History`](crate::history::History).
+    Undo,
+    Redo,
     Restart,
 }
 
+/// No layout the solver clears from the first click turned up in
+/// [`NO_GUESS_ATTEMPTS`] draws. The board is left unseeded and unchanged, so
+/// the player can click elsewhere or pick easier settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NoGuessError {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for NoGuessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no layout in {NO_GUESS_ATTEMPTS} draws clears without guessing from {} {}; \
+             try another cell or fewer mines",
+            self.row, self.col
+        )
+    }
+}
+
 /// Row-major grid held in a single flat allocation, indexed `row * width + col`
 /// rather than as a `Vec<Vec<_>>` of independently allocated rows.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct Board {
     config: Config,
     cells: Vec<CellState>,
     seeded: bool,
 }
 
+/// The player's rows rather than the cells, as for `CellState`.
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Board")
+            .field("config", &self.config)
+            .field("rows", &self.view_rows().collect::<Vec<_>>())
+            .field("seeded", &self.seeded)
+            .finish()
+    }
+}
+
 impl Board {
     /// An empty, unseeded board. Mines are laid on the first reveal so that the
-    /// opening click can be guaranteed safe.
+    /// opening click can be given what the config's [`FirstClick`] promises.
     pub fn new(config: Config) -> Self {
         Self {
+            cells: vec![CellState::default(); config.area()],
             config,
-            cells: vec![CellState::default(); config.cells()],
             seeded: false,
         }
     }
 
-    pub fn config(&self) -> Config {
-        self.config
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Whether the mines have been laid, which happens on the first reveal.
+    pub fn is_seeded(&self) -> bool {
+        self.seeded
+    }
+
+    /// Rebuilds a board from stored cells, recounting every number from the
+    /// mines rather than trusting the counts it was given. `cells` must hold
+    /// exactly `config.area()` entries in row-major order, gaps included.
+    pub(crate) fn restore(config: Config, cells: Vec<CellState>, seeded: bool) -> Self {
+        debug_assert_eq!(cells.len(), config.area());
+        let mut board = Self {
+            config,
+            cells,
+            seeded,
+        };
+        board.count_adjacent();
+        board
+    }
+
+    /// What the player sees at `(row, col)`.
+    pub fn view(&self, row: usize, col: usize) -> PlayerView {
+        match self.contains(row, col) {
+            true => PlayerView::of(&self.cell(row, col)),
+            false => PlayerView::Gap,
+        }
+    }
+
+    /// Every row as the player sees it, gaps included.
+    pub fn view_rows(&self) -> impl Iterator<Item = Vec<PlayerView>> + '_ {
+        (0..self.config.rows()).map(move |row| {
+            (0..self.config.width)
+                .map(|col| self.view(row, col))
+                .collect()
+        })
     }
 
-    pub fn rows(&self) -> impl Iterator<Item = &[CellState]> {
+    /// Everything the player can see, to hand to the solver or the
+    /// probability engine.
+    pub fn player_board(&self) -> PlayerBoard {
+        PlayerBoard {
+            config: self.config.clone(),
+            views: self.view_rows().flatten().collect(),
+        }
+    }
+
+    /// Every stored row, gaps included; [`Board::contains`] tells them apart.
+    /// These are the rules' own cells, mines and all: anything that shows or
+    /// plays the board reads [`Board::view`] instead.
+    pub(crate) fn rows(&self) -> impl Iterator<Item = &[CellState]> {
         self.cells.chunks(self.config.width)
     }
 
-    /// Mines not yet accounted for by a flag; goes negative if the player
+    /// Mines not yet accounted for by a flag or an explosion, counting each
+    /// flag for as many mines as it claims; goes negative if the player
     /// over-flags, which is the standard behaviour.
     pub fn mines_remaining(&self) -> isize {
-        let flags = self.cells.iter().filter(|cell| cell.flagged).count();
-        self.config.mines as isize - flags as isize
+        let known: usize = self.cells.iter().map(CellState::known_mines).sum();
+        self.config.mines as isize - known as isize
+    }
+
+    /// Mine hits left before the next one ends the game, that one included.
+    /// A cell counts once however many mines it holds.
+    pub fn lives_remaining(&self) -> usize {
+        let hits = self.cells.iter().filter(|cell| cell.is_exploded()).count();
+        self.config.lives.saturating_sub(hits)
     }
 
+    /// Whether `(row, col)` is a cell of the board. Gaps in a mask are not,
+    /// so every action on one is ignored.
     pub fn contains(&self, row: usize, col: usize) -> bool {
-        row < self.config.height && col < self.config.width
+        self.config.contains(row, col)
     }
 
-    /// The up-to-eight surrounding coordinates, excluding the cell itself and
-    /// anything past an edge.
+    /// The distinct cells touching this one, through the grid shape and
+    /// topology; see [`geometry`].
     pub fn neighbors(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> {
-        let (rows, cols) = (self.config.height, self.config.width);
-        (row.saturating_sub(1)..(row + 2).min(rows))
-            .flat_map(move |r| (col.saturating_sub(1)..(col + 2).min(cols)).map(move |c| (r, c)))
-            .filter(move |&position| position != (row, col))
+        geometry::neighbors(&self.config, row, col)
     }
 
-    pub fn cell(&self, row: usize, col: usize) -> CellState {
+    /// One of the rules' own cells; see [`Board::rows`].
+    pub(crate) fn cell(&self, row: usize, col: usize) -> CellState {
         self.cells[self.index(row, col)]
     }
 
@@ -102,109 +342,284 @@ impl Board {
     }
 
     fn is_mine(&self, row: usize, col: usize) -> bool {
-        self.cell(row, col).content == Cell::Mine
+        self.cell(row, col).content.is_mine()
     }
 
-    /// Toggles a flag. Uncovered cells are inert, which stops a mis-click from
-    /// hiding a number the player already earned.
+    /// Cycles the mark on a covered cell: flag, then a flag for one more mine
+    /// up to the config's stack size, then question mark if the config allows
+    /// them, then nothing. Uncovered cells are inert, which stops
+    /// a mis-click from hiding a number the player already earned.
     pub fn toggle_flag(&mut self, row: usize, col: usize) {
         if !self.contains(row, col) {
             return;
         }
         let index = self.index(row, col);
-        if !self.cells[index].uncovered {
-            self.cells[index].flagged = !self.cells[index].flagged;
+        if self.cells[index].uncovered {
+            return;
         }
+        self.cells[index].mark = match self.cells[index].mark {
+            Mark::None => Mark::Flag(1),
+            Mark::Flag(count) if (count as usize) < self.config.mines_per_cell => {
+                Mark::Flag(count + 1)
+            }
+            Mark::Flag(_) if self.config.question_marks => Mark::Question,
+            Mark::Flag(_) | Mark::Question => Mark::None,
+        };
     }
 
-    pub fn reveal<R: Rng>(&mut self, row: usize, col: usize, rng: &mut R) -> Option<GameResult> {
+    pub fn reveal(&mut self, row: usize, col: usize, rng: &mut Generator) -> Option<GameResult> {
         if !self.contains(row, col) {
             return None;
         }
         let cell = self.cell(row, col);
-        if cell.flagged || cell.uncovered {
+        if cell.is_flagged() || cell.uncovered {
             return None;
         }
         // Seeded only once the click is known to reveal something, or a click
         // rejected above would spend the first-click guarantee on nothing.
-        if !self.seeded {
-            self.seed((row, col), rng);
+        if self.lay_mines(row, col, rng).is_err() {
+            return None;
         }
         if self.is_mine(row, col) {
-            return self.lose();
+            return self.explode(row, col);
         }
         self.flood(row, col);
         self.is_cleared().then_some(GameResult::Won)
     }
 
-    /// Lays mines uniformly at random, excluding the opening click and its
-    /// neighbours. O(n) in the number of cells via a partial Fisher-Yates draw.
-    fn seed<R: Rng>(&mut self, safe: (usize, usize), rng: &mut R) {
-        let reserved: Vec<usize> = self
-            .neighbors(safe.0, safe.1)
-            .chain(std::iter::once(safe))
-            .map(|(row, col)| self.index(row, col))
+    /// Reveals every covered, unflagged neighbour of an uncovered number once
+    /// the player's flags and exploded mines around it account for exactly
+    /// that many mines. A misplaced flag lets a mine through, and that costs a
+    /// life like any other reveal; the mines go off before anything opens, so
+    /// a chord that spends the last life opens nothing.
+    pub fn chord(&mut self, row: usize, col: usize) -> Option<GameResult> {
+        if !self.contains(row, col) {
+            return None;
+        }
+        let cell = self.cell(row, col);
+        let Cell::Adjacent(count) = cell.content else {
+            return None;
+        };
+        let known: usize = self
+            .neighbors(row, col)
+            .map(|(r, c)| self.cell(r, c).known_mines())
+            .sum();
+        if !cell.uncovered || known != count as usize {
+            return None;
+        }
+        let targets: Vec<(usize, usize)> = self
+            .neighbors(row, col)
+            .filter(|&(r, c)| {
+                let cell = self.cell(r, c);
+                !cell.uncovered && !cell.is_flagged()
+            })
             .collect();
-        let candidates: Vec<usize> = (0..self.cells.len())
+        if targets.is_empty() {
+            return None;
+        }
+        let (mines, safe): (Vec<_>, Vec<_>) =
+            targets.into_iter().partition(|&(r, c)| self.is_mine(r, c));
+        for (r, c) in mines {
+            if let Some(result) = self.explode(r, c) {
+                return Some(result);
+            }
+        }
+        for (r, c) in safe {
+            self.flood(r, c);
+        }
+        self.is_cleared().then_some(GameResult::Won)
+    }
+
+    /// Lays the mines for a first click at `(row, col)`, unless they are laid
+    /// already; [`Board::reveal`] does this itself, and a no-guess board that
+    /// finds no layout reveals nothing. Front ends call it first to tell the
+    /// player why.
+    pub fn lay_mines(
+        &mut self,
+        row: usize,
+        col: usize,
+        rng: &mut Generator,
+    ) -> Result<(), NoGuessError> {
+        match self.seeded {
+            true => Ok(()),
+            false => self.seed((row, col), rng),
+        }
+    }
+
+    /// Lays mines the way `rng`'s [`Version`] does. Every step that decides
+    /// where a mine ends up belongs to the version, not just the random
+    /// stream, so a new way of reserving, relocating or accepting a layout
+    /// is a new arm here and old seeds keep drawing their old boards.
+    fn seed(&mut self, safe: (usize, usize), rng: &mut Generator) -> Result<(), NoGuessError> {
+        match rng.version() {
+            Version::V1 => self.seed_v1(safe, rng),
+        }
+    }
+
+    /// Lays mines uniformly at random, excluding gaps and whatever the
+    /// first-click policy reserves around the opening click. A cell that may
+    /// hold several mines is drawn as that many slots, so stacks are as likely
+    /// as the draw makes them. O(n) in the number of cells via a partial
+    /// Fisher-Yates draw. A no-guess config redraws until the version 1
+    /// solver can clear the board from `safe`, up to [`NO_GUESS_ATTEMPTS`]
+    /// times, and never falls back to a layout that needs a guess: it fails
+    /// and leaves the board as it was. [`Config::check`] keeps no-guess boards
+    /// to shapes, sizes and densities where a few dozen draws are usually
+    /// plenty. Every draw comes from `rng`, so a seeded generator still
+    /// reproduces the board.
+    ///
+    /// The candidates run in row-major order and the reserve only filters
+    /// them, so the order [`geometry::neighbors`] lists cells in never reaches
+    /// the draw; relocation walks row-major order too.
+    fn seed_v1(&mut self, safe: (usize, usize), rng: &mut Generator) -> Result<(), NoGuessError> {
+        let reserved: Vec<usize> = match self.config.first_click {
+            FirstClick::Unprotected | FirstClick::Relocate => Vec::new(),
+            FirstClick::SafeCell => vec![self.index(safe.0, safe.1)],
+            FirstClick::Opening => self
+                .neighbors(safe.0, safe.1)
+                .chain(std::iter::once(safe))
+                .map(|(row, col)| self.index(row, col))
+                .collect(),
+        };
+        let candidates: Vec<usize> = self
+            .config
+            .positions()
+            .map(|(row, col)| self.index(row, col))
             .filter(|index| !reserved.contains(index))
+            .flat_map(|index| std::iter::repeat_n(index, self.config.mines_per_cell))
+            .collect();
+        for _ in 0..NO_GUESS_ATTEMPTS {
+            self.lay(&candidates, rng);
+            if self.config.first_click == FirstClick::Relocate {
+                self.relocate(safe);
+            }
+            self.count_adjacent();
+            if self.config.generation == Generation::Uniform
+                || self.solvable_from(safe, Version::V1)
+            {
+                self.seeded = true;
+                return Ok(());
+            }
+        }
+        for cell in self.cells.iter_mut() {
+            cell.content = Cell::default();
+        }
+        Err(NoGuessError {
+            row: safe.0,
+            col: safe.1,
+        })
+    }
+
+    fn lay(&mut self, candidates: &[usize], rng: &mut Generator) {
+        for cell in self.cells.iter_mut() {
+            cell.content = Cell::default();
+        }
+        for index in rng.choose(candidates, self.config.mines) {
+            let content = &mut self.cells[index].content;
+            *content = Cell::Mine(content.mines() as u8 + 1);
+        }
+    }
+
+    /// Moves every mine under `safe` to the first cells in row-major order
+    /// with room for another, as the Windows game does. The capacity check
+    /// leaves room for all of them outside `safe`.
+    fn relocate(&mut self, safe: (usize, usize)) {
+        let index = self.index(safe.0, safe.1);
+        let mut left = self.cells[index].content.mines();
+        self.cells[index].content = Cell::default();
+        let targets: Vec<usize> = self
+            .config
+            .positions()
+            .map(|(row, col)| self.index(row, col))
+            .filter(|&target| target != index)
             .collect();
-        for &index in candidates.choose_multiple(rng, self.config.mines) {
-            self.cells[index].content = Cell::Mine;
+        for target in targets {
+            let content = &mut self.cells[target].content;
+            while left > 0 && content.mines() < self.config.mines_per_cell {
+                *content = Cell::Mine(content.mines() as u8 + 1);
+                left -= 1;
+            }
+        }
+    }
+
+    /// Plays a copy of the board, marks wiped, by the deductions `version`
+    /// accepts no-guess layouts with.
+    fn solvable_from(&self, start: (usize, usize), version: Version) -> bool {
+        let mut trial = self.clone();
+        for cell in trial.cells.iter_mut() {
+            cell.mark = Mark::None;
         }
-        self.count_adjacent();
-        self.seeded = true;
+        solver::clears_without_guessing(&mut trial, start, version)
     }
 
     fn count_adjacent(&mut self) {
         let width = self.config.width;
-        let counts: Vec<u8> = (0..self.cells.len())
+        let counts: Vec<u16> = (0..self.cells.len())
             .map(|index| self.adjacent_mines(index / width, index % width))
             .collect();
         for (cell, count) in self.cells.iter_mut().zip(counts) {
-            if cell.content != Cell::Mine {
+            if !cell.content.is_mine() {
                 cell.content = Cell::Adjacent(count);
             }
         }
     }
 
-    fn adjacent_mines(&self, row: usize, col: usize) -> u8 {
+    fn adjacent_mines(&self, row: usize, col: usize) -> u16 {
         self.neighbors(row, col)
-            .filter(|&(r, c)| self.is_mine(r, c))
-            .count() as u8
+            .map(|(r, c)| self.cell(r, c).content.mines())
+            .sum::<usize>() as u16
     }
 
     /// Iterative flood fill over an explicit stack. Recursion would blow the
     /// wasm stack on a large blank region, and an explicit stack also makes the
     /// visit order deterministic.
-    fn flood(&mut self, row: usize, col: usize) {
+    pub(crate) fn flood(&mut self, row: usize, col: usize) {
         let mut stack = vec![(row, col)];
         while let Some((row, col)) = stack.pop() {
             let index = self.index(row, col);
-            if self.cells[index].uncovered || self.cells[index].flagged {
+            if self.cells[index].uncovered || self.cells[index].is_flagged() {
                 continue;
             }
+            // A question mark is only a note, so the sweep opens it and wipes it.
             self.cells[index].uncovered = true;
+            self.cells[index].mark = Mark::None;
             if self.cells[index].content == Cell::Adjacent(0) {
                 stack.extend(self.neighbors(row, col));
             }
         }
     }
 
+    /// Sets off the mine at `(row, col)`. It stays uncovered, as a mine the
+    /// player now knows, unless it spent the last life and the game is lost.
+    fn explode(&mut self, row: usize, col: usize) -> Option<GameResult> {
+        let index = self.index(row, col);
+        self.cells[index].uncovered = true;
+        self.cells[index].mark = Mark::None;
+        match self.lives_remaining() {
+            0 => self.lose(),
+            _ => None,
+        }
+    }
+
+    /// Exposes every mine. Marks are only ever on covered cells, so any flag on
+    /// a mine goes with it.
     fn lose(&mut self) -> Option<GameResult> {
         for cell in self.cells.iter_mut() {
-            if cell.content == Cell::Mine {
+            if cell.content.is_mine() {
                 cell.uncovered = true;
+                cell.mark = Mark::None;
             }
         }
         Some(GameResult::Lost)
     }
 
     /// Won when every non-mine cell is uncovered. Flags are irrelevant, exactly
-    /// as in the original game.
-    fn is_cleared(&self) -> bool {
-        self.cells
-            .iter()
-            .all(|cell| cell.uncovered || cell.content == Cell::Mine)
+    /// as in the original game, and gaps have nothing to uncover.
+    pub(crate) fn is_cleared(&self) -> bool {
+        self.config.positions().all(|(row, col)| {
+            let cell = self.cell(row, col);
+            cell.uncovered || cell.content.is_mine()
+        })
     }
 }
 
@@ -228,16 +643,19 @@ impl GameState {
         self.result.is_some()
     }
 
-    pub fn apply<R: Rng>(&self, action: Action, rng: &mut R) -> Self {
+    pub fn apply(&self, action: Action, rng: &mut Generator) -> Self {
         match action {
-            Action::Restart => Self::new(self.board.config()),
+            Action::Restart => Self::new(self.board.config().clone()),
+            // A lone state has no past to step through; `History` handles these.
+            Action::Undo | Action::Redo => self.clone(),
             _ if self.is_over() => self.clone(),
             Action::Reveal(row, col) => self.revealed(row, col, rng),
             Action::Flag(row, col) => self.flagged(row, col),
+            Action::Chord(row, col) => self.chorded(row, col),
         }
     }
 
-    fn revealed<R: Rng>(&self, row: usize, col: usize, rng: &mut R) -> Self {
+    fn revealed(&self, row: usize, col: usize, rng: &mut Generator) -> Self {
         let mut next = self.clone();
         next.result = next.board.reveal(row, col, rng);
         next
@@ -248,41 +666,110 @@ impl GameState {
         next.board.toggle_flag(row, col);
         next
     }
+
+    fn chorded(&self, row: usize, col: usize) -> Self {
+        let mut next = self.clone();
+        next.result = next.board.chord(row, col);
+        next
+    }
 }
 
+/// Picture-built boards shared by the test suites of every rules module.
 #[cfg(test)]
-mod tests {
+pub(crate) mod fixtures {
     use super::*;
-    use rand::rngs::StdRng;
-    use rand::SeedableRng;
+    use crate::config::Mask;
+    use crate::generator::Generator;
 
-    fn rng() -> StdRng {
-        StdRng::seed_from_u64(0xC0FFEE)
+    /// The generator for tests whose boards are already laid out, so the draw
+    /// never decides anything.
+    pub(crate) fn rng() -> Generator {
+        Generator::new(0xC0FFEE)
     }
 
-    /// Builds a fully seeded board from a picture: `*` is a mine, `.` is not.
-    fn layout(rows: &[&str]) -> Board {
+    /// Builds a fully seeded board from a picture: `*` is a mine, `.` is a
+    /// covered safe cell, `o` an uncovered one, `x` an uncovered mine and `-`
+    /// a gap in the board's mask. A digit from 2 is a covered stack of that
+    /// many mines, and the config stacks as high as the tallest.
+    pub(crate) fn layout(rows: &[&str]) -> Board {
         let cells: Vec<CellState> = rows.iter().flat_map(|row| row.bytes()).map(cell).collect();
-        let mines = cells.iter().filter(|c| c.content == Cell::Mine).count();
+        let mines = cells.iter().map(|c| c.content.mines()).sum();
+        let tallest = cells.iter().map(|c| c.content.mines()).max().unwrap_or(0);
         let config = Config::new(rows[0].len(), rows.len(), 0).expect("test layout dimensions");
-        let mut board = Board::new(config);
-        board.config = Config { mines, ..config };
+        let mut board = Board::new(Config {
+            mines,
+            mines_per_cell: tallest.max(1),
+            mask: mask(rows),
+            ..config
+        });
         board.cells = cells;
         board.count_adjacent();
         board.seeded = true;
         board
     }
 
+    /// [`layout`] with its config adjusted by `tweak`, for settings that do not
+    /// change the picture.
+    /// Counts are redone afterwards, since a topology can join cells the
+    /// picture shows apart.
+    pub(crate) fn layout_with(rows: &[&str], tweak: impl FnOnce(Config) -> Config) -> Board {
+        let mut board = layout(rows);
+        board.config = tweak(board.config);
+        board.count_adjacent();
+        board
+    }
+
+    /// [`layout`] for a cube: one picture per layer, top layer first.
+    pub(crate) fn cube(layers: &[&[&str]]) -> Board {
+        let rows = layers.concat();
+        let (height, depth) = (layers[0].len(), layers.len());
+        layout_with(&rows, |config| Config {
+            height,
+            depth,
+            ..config
+        })
+    }
+
+    /// The picture's shape, or `None` if it has no gaps.
+    fn mask(rows: &[&str]) -> Option<Mask> {
+        let shape: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|glyph| match glyph {
+                        Mask::GAP => Mask::GAP,
+                        _ => Mask::CELL,
+                    })
+                    .collect()
+            })
+            .collect();
+        let shape: Vec<&str> = shape.iter().map(String::as_str).collect();
+        match rows.iter().any(|row| row.contains(Mask::GAP)) {
+            true => Some(Mask::from_rows(&shape).expect("test layout shape")),
+            false => None,
+        }
+    }
+
     fn cell(byte: u8) -> CellState {
         let content = match byte {
-            b'*' => Cell::Mine,
+            b'*' | b'x' => Cell::Mine(1),
+            b'2'..=b'9' => Cell::Mine(byte - b'0'),
             _ => Cell::default(),
         };
         CellState {
             content,
+            uncovered: matches!(byte, b'o' | b'x'),
             ..CellState::default()
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::{cube, layout, layout_with, rng};
+    use super::*;
+    use crate::config::{Grid, Kernel, Mask, Topology, MOST_NO_GUESS_DENSITY};
+    use crate::generator::Generator;
 
     fn covered(board: &Board, row: usize, col: usize) -> bool {
         !board.cell(row, col).uncovered
@@ -303,6 +790,115 @@ mod tests {
         assert_eq!(board.neighbors(2, 2).count(), 3);
     }
 
+    #[test]
+    fn corner_cells_have_eight_neighbors_on_a_torus() {
+        let board = layout_with(&["....", "....", "...."], |config| {
+            config.with_topology(Topology::Torus)
+        });
+        for (row, col) in [(0, 0), (0, 3), (2, 0), (2, 3)] {
+            assert_eq!(board.neighbors(row, col).count(), 8);
+        }
+        assert!(board.neighbors(0, 0).any(|position| position == (2, 3)));
+    }
+
+    #[test]
+    fn a_cylinder_joins_the_side_edges_but_not_the_top_and_bottom() {
+        let board = layout_with(&["....", "....", "...."], |config| {
+            config.with_topology(Topology::Cylinder)
+        });
+        assert_eq!(board.neighbors(0, 0).count(), 5);
+        assert_eq!(board.neighbors(1, 0).count(), 8);
+        assert!(board.neighbors(1, 0).any(|position| position == (0, 3)));
+    }
+
+    #[test]
+    fn a_wrapped_neighbor_is_listed_once_on_a_narrow_torus() {
+        let board = layout_with(&["..", ".."], |config| {
+            config.with_topology(Topology::Torus)
+        });
+        assert_eq!(board.neighbors(0, 0).count(), 3);
+        let board = layout_with(&["...."], |config| config.with_topology(Topology::Torus));
+        assert_eq!(board.neighbors(0, 0).count(), 2);
+    }
+
+    #[test]
+    fn adjacency_counts_reach_across_a_joined_edge() {
+        let rows = ["*...", "....", "....", "...."];
+        assert_eq!(layout(&rows).cell(3, 3).content, Cell::Adjacent(0));
+        let torus = layout_with(&rows, |config| config.with_topology(Topology::Torus));
+        assert_eq!(torus.cell(3, 3).content, Cell::Adjacent(1));
+        assert_eq!(torus.cell(0, 3).content, Cell::Adjacent(1));
+    }
+
+    #[test]
+    fn flood_fill_crosses_a_joined_edge() {
+        let rows = ["..*..", "..*..", "..*.."];
+        let mut flat = layout(&rows);
+        assert_eq!(flat.reveal(0, 0, &mut rng()), None);
+        assert!(covered(&flat, 0, 4));
+        let mut cylinder = layout_with(&rows, |config| config.with_topology(Topology::Cylinder));
+        assert_eq!(cylinder.reveal(0, 0, &mut rng()), Some(GameResult::Won));
+    }
+
+    #[test]
+    fn a_hex_blank_opens_past_where_a_square_board_stops() {
+        let rows = ["....", "...*", "...."];
+        let mut square = layout(&rows);
+        assert_eq!(square.cell(0, 2).content, Cell::Adjacent(1));
+        assert_eq!(square.reveal(0, 0, &mut rng()), None);
+        assert!(covered(&square, 0, 3));
+        let mut hex = layout_with(&rows, |config| config.with_grid(Grid::Hex));
+        assert_eq!(hex.cell(0, 2).content, Cell::Adjacent(0));
+        assert_eq!(hex.reveal(0, 0, &mut rng()), Some(GameResult::Won));
+    }
+
+    #[test]
+    fn a_knight_sweeper_counts_and_floods_by_knight_moves() {
+        let rows = ["*...", "....", "...."];
+        let mut moore = layout(&rows);
+        assert_eq!(moore.cell(1, 1).content, Cell::Adjacent(1));
+        assert_eq!(moore.reveal(1, 1, &mut rng()), None);
+        let mut knight = layout_with(&rows, |config| config.with_kernel(Kernel::Knight));
+        assert_eq!(knight.cell(1, 1).content, Cell::Adjacent(0));
+        assert_eq!(knight.cell(1, 2).content, Cell::Adjacent(1));
+        assert_eq!(knight.reveal(1, 1, &mut rng()), Some(GameResult::Won));
+    }
+
+    #[test]
+    fn a_gap_is_never_counted_revealed_or_marked_and_the_win_ignores_it() {
+        let mut board = layout(&["...", ".-.", "..*"]);
+        assert_eq!(board.cell(1, 2).content, Cell::Adjacent(1));
+        board.toggle_flag(1, 1);
+        assert_eq!(board.reveal(1, 1, &mut rng()), None);
+        assert_eq!(board.cell(1, 1), CellState::default());
+        // The flood runs round the gap, and the gap left covered does not
+        // hold up the win.
+        assert_eq!(board.reveal(0, 0, &mut rng()), Some(GameResult::Won));
+        assert!(covered(&board, 1, 1));
+    }
+
+    #[test]
+    fn a_cell_in_the_middle_of_a_cube_counts_all_twenty_six() {
+        let board = cube(&[
+            &["***", "***", "***"],
+            &["***", "*.*", "***"],
+            &["***", "***", "***"],
+        ]);
+        assert_eq!(board.cell(4, 1).content, Cell::Adjacent(26));
+    }
+
+    #[test]
+    fn flood_fill_and_the_win_reach_through_every_layer() {
+        let mut board = cube(&[&["...", "...", "..."], &["...", "...", "..*"]]);
+        assert_eq!(board.cell(2, 2).content, Cell::Adjacent(1));
+        assert_eq!(board.reveal(0, 0, &mut rng()), None);
+        assert!(!covered(&board, 3, 0), "the blank below opens");
+        assert!(!covered(&board, 5, 1), "so does the far layer's border");
+        // Directly above the mine, every neighbour is a number.
+        assert!(covered(&board, 2, 2));
+        assert_eq!(board.reveal(2, 2, &mut rng()), Some(GameResult::Won));
+    }
+
     #[test]
     fn adjacency_counts_do_not_wrap_around_the_row_boundary() {
         // Without row-major bounds checks, (0,2) and (1,0) would look adjacent.
@@ -362,6 +958,22 @@ mod tests {
         assert!(covered(&board, 1, 1), "safe cells are not given away");
     }
 
+    #[test]
+    fn the_player_view_hides_every_mine_until_the_loss_shows_them() {
+        let mut board = layout(&["*.-", "...", "..*"]);
+        board.toggle_flag(2, 2);
+        assert_eq!(board.reveal(1, 0, &mut rng()), None);
+        let views: Vec<Vec<PlayerView>> = board.view_rows().collect();
+        assert_eq!(views[0][0], PlayerView::Covered);
+        assert_eq!(views[0][2], PlayerView::Gap);
+        assert_eq!(views[1][0], PlayerView::Number(1));
+        assert_eq!(views[2][2], PlayerView::Flagged(1));
+        assert_eq!(board.reveal(0, 0, &mut rng()), Some(GameResult::Lost));
+        assert_eq!(board.view(0, 0), PlayerView::Mine(1));
+        assert_eq!(board.view(2, 2), PlayerView::Mine(1));
+        assert!(board.view(1, 1).is_covered());
+    }
+
     #[test]
     fn a_win_needs_every_safe_cell_and_is_indifferent_to_flags() {
         let mut board = layout(&["*..", "...", "..."]);
@@ -385,7 +997,49 @@ mod tests {
         let mut board = layout(&["...", "...", "..."]);
         board.reveal(1, 1, &mut rng());
         board.toggle_flag(1, 1);
-        assert!(!board.cell(1, 1).flagged);
+        assert!(!board.cell(1, 1).is_flagged());
+    }
+
+    #[test]
+    fn without_question_marks_a_mark_only_toggles_the_flag() {
+        let mut board = layout(&["...", "...", "..."]);
+        board.toggle_flag(0, 0);
+        assert_eq!(board.cell(0, 0).mark, Mark::Flag(1));
+        board.toggle_flag(0, 0);
+        assert_eq!(board.cell(0, 0).mark, Mark::None);
+    }
+
+    #[test]
+    fn with_question_marks_a_mark_cycles_flag_then_question_then_nothing() {
+        let mut board = layout(&["...", "...", "..."]);
+        board.config = board.config.with_question_marks(true);
+        let mut cycle = Vec::new();
+        for _ in 0..3 {
+            board.toggle_flag(0, 0);
+            cycle.push(board.cell(0, 0).mark);
+        }
+        assert_eq!(cycle, [Mark::Flag(1), Mark::Question, Mark::None]);
+    }
+
+    #[test]
+    fn a_question_mark_neither_counts_as_a_flag_nor_blocks_a_reveal() {
+        let mut board = layout(&["*..", "...", "..."]);
+        board.config = board.config.with_question_marks(true);
+        board.toggle_flag(0, 0);
+        board.toggle_flag(0, 0);
+        assert_eq!(board.mines_remaining(), 1);
+        assert_eq!(board.reveal(0, 0, &mut rng()), Some(GameResult::Lost));
+    }
+
+    #[test]
+    fn flood_fill_opens_question_marked_cells_and_clears_the_mark() {
+        let mut board = layout(&["....", "....", "...."]);
+        board.config = board.config.with_question_marks(true);
+        board.toggle_flag(2, 3);
+        board.toggle_flag(2, 3);
+        board.reveal(0, 0, &mut rng());
+        assert!(!covered(&board, 2, 3));
+        assert_eq!(board.cell(2, 3).mark, Mark::None);
     }
 
     #[test]
@@ -403,7 +1057,67 @@ mod tests {
         let mut board = layout(&["...", "...", "..."]);
         assert_eq!(board.reveal(3, 0, &mut rng()), None);
         board.toggle_flag(0, 99);
-        assert!(board.rows().flatten().all(|cell| !cell.flagged));
+        assert!(board.rows().flatten().all(|cell| !cell.is_flagged()));
+    }
+
+    #[test]
+    fn chording_a_satisfied_number_reveals_its_unflagged_neighbors() {
+        let mut board = layout(&["*..*", "....", "....", "*..*"]);
+        board.reveal(1, 1, &mut rng());
+        board.toggle_flag(0, 0);
+        assert_eq!(board.chord(1, 1), None);
+        assert!(!covered(&board, 0, 1));
+        assert!(!covered(&board, 2, 2));
+        assert!(covered(&board, 0, 0), "the flagged mine stays covered");
+        assert!(
+            covered(&board, 3, 1),
+            "only the chorded cell's neighbours open"
+        );
+    }
+
+    #[test]
+    fn chording_floods_through_a_blank_neighbor() {
+        let mut board = layout(&["*....", ".....", ".....", "....."]);
+        board.reveal(0, 1, &mut rng());
+        board.toggle_flag(0, 0);
+        assert_eq!(board.chord(0, 1), Some(GameResult::Won));
+        assert!(
+            !covered(&board, 3, 4),
+            "the blank neighbour opened its region"
+        );
+    }
+
+    #[test]
+    fn chording_needs_exactly_as_many_flags_as_the_number() {
+        let mut board = layout(&["*..", "...", "..."]);
+        board.reveal(1, 1, &mut rng());
+        assert_eq!(board.chord(1, 1), None);
+        assert!(covered(&board, 0, 1), "too few flags: nothing opens");
+        board.toggle_flag(0, 0);
+        board.toggle_flag(0, 1);
+        assert_eq!(board.chord(1, 1), None);
+        assert!(covered(&board, 2, 2), "too many flags: nothing opens");
+    }
+
+    #[test]
+    fn chording_past_a_misplaced_flag_loses() {
+        let mut board = layout(&["*..", "...", "..."]);
+        board.reveal(1, 1, &mut rng());
+        board.toggle_flag(2, 2);
+        assert_eq!(board.chord(1, 1), Some(GameResult::Lost));
+        assert!(!covered(&board, 0, 0));
+    }
+
+    #[test]
+    fn chording_a_covered_or_blank_cell_is_a_no_op() {
+        let mut board = layout(&["*..", "...", "..."]);
+        board.toggle_flag(0, 0);
+        assert_eq!(board.chord(1, 1), None);
+        assert!(covered(&board, 1, 1));
+        let mut open = layout(&["....", "....", "...*"]);
+        open.reveal(0, 0, &mut rng());
+        assert_eq!(open.chord(0, 0), None);
+        assert!(covered(&open, 2, 3));
     }
 
     #[test]
@@ -414,7 +1128,7 @@ mod tests {
         let mines = board
             .rows()
             .flatten()
-            .filter(|cell| cell.content == Cell::Mine)
+            .filter(|cell| cell.content.is_mine())
             .count();
         assert_eq!(mines, 20);
     }
@@ -423,8 +1137,8 @@ mod tests {
     fn the_opening_click_and_its_eight_neighbors_are_always_mine_free() {
         let config = Config::new(4, 4, 7).expect("valid config"); // maximum density
         for seed in 0..200 {
-            let mut board = Board::new(config);
-            let outcome = board.reveal(1, 1, &mut StdRng::seed_from_u64(seed));
+            let mut board = Board::new(config.clone());
+            let outcome = board.reveal(1, 1, &mut Generator::new(seed));
             assert_ne!(
                 outcome,
                 Some(GameResult::Lost),
@@ -433,23 +1147,185 @@ mod tests {
             let safe = board.neighbors(1, 1).chain(std::iter::once((1, 1)));
             assert!(safe
                 .map(|(r, c)| board.cell(r, c))
-                .all(|cell| cell.content != Cell::Mine));
+                .all(|cell| !cell.content.is_mine()));
+        }
+    }
+
+    #[test]
+    fn the_opening_click_in_a_torus_corner_keeps_its_wrapped_neighbors_clear() {
+        let config = Config::new(5, 5, 16)
+            .expect("valid config") // maximum density
+            .with_topology(Topology::Torus);
+        for seed in 0..200 {
+            let mut board = Board::new(config.clone());
+            assert_ne!(
+                board.reveal(0, 0, &mut Generator::new(seed)),
+                Some(GameResult::Lost)
+            );
+            assert!(board
+                .neighbors(0, 0)
+                .all(|(r, c)| !board.cell(r, c).content.is_mine()));
+        }
+    }
+
+    #[test]
+    fn a_hex_opening_click_keeps_its_six_neighbors_clear() {
+        let config = Config::new(5, 5, 16)
+            .expect("valid config")
+            .with_grid(Grid::Hex);
+        let config = Config {
+            mines: 18,
+            ..config
+        }
+        .check()
+        .expect("maximum hex density");
+        for seed in 0..200 {
+            let mut board = Board::new(config.clone());
+            assert_ne!(
+                board.reveal(2, 2, &mut Generator::new(seed)),
+                Some(GameResult::Lost)
+            );
+            assert_eq!(board.neighbors(2, 2).count(), 6);
+            assert!(board
+                .neighbors(2, 2)
+                .all(|(r, c)| !board.cell(r, c).content.is_mine()));
+        }
+    }
+
+    #[test]
+    fn a_cube_opening_click_keeps_all_twenty_six_neighbors_clear() {
+        let config = Config::new(4, 4, 0).expect("valid config").with_depth(4);
+        let config = Config {
+            mines: 37,
+            ..config
+        }
+        .check()
+        .expect("maximum cube density");
+        for seed in 0..200 {
+            let mut board = Board::new(config.clone());
+            assert_ne!(
+                board.reveal(5, 1, &mut Generator::new(seed)),
+                Some(GameResult::Lost)
+            );
+            assert_eq!(board.neighbors(5, 1).count(), 26);
+            assert!(board
+                .neighbors(5, 1)
+                .all(|(r, c)| !board.cell(r, c).content.is_mine()));
+        }
+    }
+
+    #[test]
+    fn a_radius_two_opening_click_keeps_all_twenty_four_neighbors_clear() {
+        let config = Config::new(7, 7, 0)
+            .expect("valid config")
+            .with_kernel(Kernel::Radius2);
+        let config = Config {
+            mines: 24,
+            ..config
+        }
+        .check()
+        .expect("maximum radius-2 density");
+        for seed in 0..200 {
+            let mut board = Board::new(config.clone());
+            assert_ne!(
+                board.reveal(3, 3, &mut Generator::new(seed)),
+                Some(GameResult::Lost)
+            );
+            assert_eq!(board.neighbors(3, 3).count(), 24);
+            assert!(board
+                .neighbors(3, 3)
+                .all(|(r, c)| !board.cell(r, c).content.is_mine()));
+        }
+    }
+
+    #[test]
+    fn mines_are_only_laid_in_the_cells_a_mask_keeps() {
+        let mask = Mask::from_rows(&["-###-", "#####", "#####", "#####", "-###-"]);
+        let config = Config::new(5, 5, 0).expect("valid config").with_mask(mask);
+        let config = Config {
+            mines: config.capacity(),
+            ..config
+        };
+        for seed in 0..200 {
+            let mut board = Board::new(config.clone());
+            assert_ne!(
+                board.reveal(2, 2, &mut Generator::new(seed)),
+                Some(GameResult::Lost)
+            );
+            let gaps = [(0, 0), (0, 4), (4, 0), (4, 4)];
+            assert!(gaps
+                .iter()
+                .all(|&(r, c)| !board.cell(r, c).content.is_mine()));
+            let mines = config
+                .positions()
+                .filter(|&(r, c)| board.cell(r, c).content.is_mine())
+                .count();
+            assert_eq!(mines, 12);
+        }
+    }
+
+    fn policy(first_click: FirstClick, mines: usize) -> Config {
+        let config = Config::new(4, 4, 0)
+            .expect("valid config")
+            .with_first_click(first_click);
+        Config { mines, ..config }.check().expect("mines fit")
+    }
+
+    #[test]
+    fn an_unprotected_first_click_can_lose() {
+        let config = policy(FirstClick::Unprotected, 15);
+        let lost = (0..50).any(|seed| {
+            let mut board = Board::new(config.clone());
+            board.reveal(1, 1, &mut Generator::new(seed)) == Some(GameResult::Lost)
+        });
+        assert!(lost);
+    }
+
+    #[test]
+    fn a_safe_cell_first_click_may_land_on_a_number() {
+        let config = policy(FirstClick::SafeCell, 15);
+        for seed in 0..50 {
+            let mut board = Board::new(config.clone());
+            let outcome = board.reveal(1, 1, &mut Generator::new(seed));
+            assert_eq!(outcome, Some(GameResult::Won), "seed {seed}");
+            assert_eq!(board.cell(1, 1).content, Cell::Adjacent(8));
+        }
+    }
+
+    #[test]
+    fn a_relocated_mine_moves_to_the_first_free_cell_from_the_top_left() {
+        let mut moved = 0;
+        for seed in 0..200 {
+            let mut unprotected = Board::new(policy(FirstClick::Unprotected, 1));
+            let mut relocated = Board::new(policy(FirstClick::Relocate, 1));
+            let hit = unprotected.reveal(2, 2, &mut Generator::new(seed));
+            let outcome = relocated.reveal(2, 2, &mut Generator::new(seed));
+            assert_ne!(outcome, Some(GameResult::Lost), "seed {seed}");
+            let mine = |board: &Board| board.config.positions().find(|&(r, c)| board.is_mine(r, c));
+            match hit {
+                Some(GameResult::Lost) => {
+                    assert_eq!(mine(&relocated), Some((0, 0)));
+                    moved += 1;
+                }
+                _ => assert_eq!(mine(&relocated), mine(&unprotected)),
+            }
         }
+        assert!(moved > 0, "no seed put the mine under the click");
     }
 
     #[test]
     fn a_click_on_a_flagged_cell_does_not_spend_first_click_safety() {
         let config = Config::new(4, 4, 7).expect("valid config"); // maximum density
         for seed in 0..200 {
-            let mut board = Board::new(config);
+            let mut board = Board::new(config.clone());
             board.toggle_flag(0, 0);
-            assert_eq!(board.reveal(0, 0, &mut StdRng::seed_from_u64(seed)), None);
+            assert_eq!(board.reveal(0, 0, &mut Generator::new(seed)), None);
             assert!(
                 !board.seeded,
                 "a click that revealed nothing laid the mines"
             );
             assert_ne!(
-                board.reveal(3, 3, &mut StdRng::seed_from_u64(seed)),
+                board.reveal(3, 3, &mut Generator::new(seed)),
                 Some(GameResult::Lost),
                 "seed {seed} put a mine under the first visible reveal"
             );
@@ -461,8 +1337,144 @@ mod tests {
         let config = Config::new(8, 8, 10).expect("valid config");
         let boards: Vec<Board> = (0..2)
             .map(|_| {
-                let mut board = Board::new(config);
-                board.reveal(0, 0, &mut StdRng::seed_from_u64(7));
+                let mut board = Board::new(config.clone());
+                board.reveal(0, 0, &mut Generator::new(7));
+                board
+            })
+            .collect();
+        assert_eq!(boards[0], boards[1]);
+    }
+
+    #[test]
+    fn a_debug_dump_of_a_game_in_play_shows_only_what_the_player_sees() {
+        let one = layout(&["*..", "...", "..."]);
+        let other = layout(&["..*", "...", "..."]);
+        assert_ne!(one, other);
+        assert_eq!(format!("{one:?}"), format!("{other:?}"));
+        assert_eq!(format!("{:?}", one.cell(0, 0)), "Covered");
+        let state = GameState {
+            board: one,
+            result: None,
+        };
+        assert!(!format!("{state:?}").contains("Mine"));
+    }
+
+    #[test]
+    fn a_player_board_holds_nothing_of_what_a_covered_cell_hides() {
+        // Same numbers either way; only the covered top row differs.
+        let one = layout(&["*.", "oo", "oo"]);
+        let other = layout(&[".*", "oo", "oo"]);
+        assert_ne!(one, other);
+        let seen = one.player_board();
+        assert_eq!(seen, other.player_board());
+        assert_eq!(seen.view(0, 0), PlayerView::Covered);
+        assert_eq!(seen.view(1, 1), PlayerView::Number(1));
+        assert_eq!(seen.view(3, 0), PlayerView::Gap);
+        assert!(!format!("{seen:?}").contains("Mine"));
+    }
+
+    #[test]
+    fn a_no_guess_board_clears_by_deduction_from_the_opening_click() {
+        let config = Config::new(9, 9, 10)
+            .expect("valid config")
+            .with_generation(Generation::NoGuess);
+        for seed in 0..20 {
+            let mut board = Board::new(config.clone());
+            assert_ne!(
+                board.reveal(4, 4, &mut Generator::new(seed)),
+                Some(GameResult::Lost)
+            );
+            assert!(
+                board.solvable_from((4, 4), Version::V1),
+                "seed {seed} left a forced guess"
+            );
+        }
+    }
+
+    #[test]
+    fn every_no_guess_board_the_config_accepts_clears_from_every_first_click() {
+        let sizes = [(4, 4), (4, 6), (5, 5), (6, 4), (6, 6)];
+        for (width, height) in sizes {
+            for grid in [Grid::Square, Grid::Hex] {
+                for topology in [Topology::Flat, Topology::Cylinder, Topology::Torus] {
+                    for policy in [
+                        FirstClick::SafeCell,
+                        FirstClick::Opening,
+                        FirstClick::Relocate,
+                    ] {
+                        let config = Config::new(
+                            width,
+                            height,
+                            width * height * MOST_NO_GUESS_DENSITY / 100,
+                        )
+                        .expect("valid config")
+                        .with_grid(grid)
+                        .with_topology(topology)
+                        .with_first_click(policy)
+                        .with_generation(Generation::NoGuess);
+                        let Ok(config) = config.check() else {
+                            continue;
+                        };
+                        for (row, col) in config.positions().collect::<Vec<_>>() {
+                            let mut board = Board::new(config.clone());
+                            let rng = &mut Generator::new(row as u64);
+                            assert_eq!(board.lay_mines(row, col, rng), Ok(()));
+                            assert!(
+                                board.solvable_from((row, col), Version::V1),
+                                "{width}x{height} {grid:?} {topology:?} {policy:?} left a \
+                                 forced guess from ({row}, {col})"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        let expert = Config::new(30, 16, 99)
+            .expect("valid config")
+            .with_generation(Generation::NoGuess);
+        for policy in [
+            FirstClick::SafeCell,
+            FirstClick::Opening,
+            FirstClick::Relocate,
+        ] {
+            let mut board = Board::new(expert.clone().with_first_click(policy));
+            assert_eq!(board.lay_mines(0, 0, &mut Generator::new(5)), Ok(()));
+            assert!(
+                board.solvable_from((0, 0), Version::V1),
+                "{policy:?} left a forced guess"
+            );
+        }
+    }
+
+    #[test]
+    fn a_no_guess_search_that_finds_nothing_fails_and_leaves_the_board_unseeded() {
+        // Past the config check: the one mine always sits on a side of the
+        // click, a 50/50 no draw escapes.
+        let config = Config::new(3, 1, 0)
+            .expect("valid config")
+            .with_first_click(FirstClick::SafeCell)
+            .with_generation(Generation::NoGuess);
+        let config = Config { mines: 1, ..config };
+        let mut board = Board::new(config.clone());
+        assert_eq!(
+            board.lay_mines(0, 1, &mut rng()),
+            Err(NoGuessError { row: 0, col: 1 })
+        );
+        assert_eq!(board, Board::new(config));
+        assert_eq!(board.reveal(0, 1, &mut rng()), None);
+        assert!(!board.is_seeded());
+        assert_eq!(board.view(0, 1), PlayerView::Covered);
+    }
+
+    #[test]
+    fn no_guess_generation_is_reproducible_for_a_fixed_seed() {
+        let config = Config::new(9, 9, 10)
+            .expect("valid config")
+            .with_generation(Generation::NoGuess);
+        let boards: Vec<Board> = (0..2)
+            .map(|_| {
+                let mut board = Board::new(config.clone());
+                board.reveal(0, 0, &mut Generator::new(3));
                 board
             })
             .collect();
@@ -482,7 +1494,7 @@ mod tests {
     #[test]
     fn restart_rebuilds_an_unseeded_board_with_the_same_configuration() {
         let config = Config::new(6, 6, 5).expect("valid config");
-        let state = GameState::new(config).apply(Action::Reveal(0, 0), &mut rng());
+        let state = GameState::new(config.clone()).apply(Action::Reveal(0, 0), &mut rng());
         let restarted = state.apply(Action::Restart, &mut rng());
         assert_eq!(restarted, GameState::new(config));
         assert!(restarted.board.rows().flatten().all(|cell| !cell.uncovered));
@@ -497,3 +1509,183 @@ mod tests {
         assert!(!state.apply(Action::Restart, &mut rng()).is_over());
     }
 }
+
+/// Boards whose cells may hold several mines.
+#[cfg(test)]
+mod stack_tests {
+    use super::fixtures::{layout, layout_with, rng};
+    use super::*;
+    use crate::config::Kernel;
+
+    #[test]
+    fn numbers_add_up_every_mine_in_every_stack() {
+        let board = layout(&["3..", ".*.", "..2"]);
+        assert_eq!(board.config().mines, 6);
+        assert_eq!(board.config().mines_per_cell, 3);
+        assert_eq!(board.cell(0, 1).content, Cell::Adjacent(4));
+        assert_eq!(board.cell(1, 2).content, Cell::Adjacent(3));
+        assert_eq!(board.cell(2, 0).content, Cell::Adjacent(1));
+    }
+
+    #[test]
+    fn a_count_can_pass_what_one_byte_holds() {
+        let full = ["99999"; 5];
+        let hollow = ["99999", "99999", "99.99", "99999", "99999"];
+        let board = layout_with(&[full, hollow, full].concat(), |config| Config {
+            height: 5,
+            depth: 3,
+            kernel: Kernel::Radius2,
+            ..config
+        });
+        assert_eq!(board.cell(7, 2).content, Cell::Adjacent(74 * 9));
+    }
+
+    #[test]
+    fn a_flag_cycles_through_every_count_before_it_comes_off() {
+        let mut board = layout_with(&["3.", ".."], |config| config.with_question_marks(true));
+        let mut cycle = Vec::new();
+        for _ in 0..5 {
+            board.toggle_flag(0, 0);
+            cycle.push(board.cell(0, 0).mark);
+        }
+        assert_eq!(
+            cycle,
+            [
+                Mark::Flag(1),
+                Mark::Flag(2),
+                Mark::Flag(3),
+                Mark::Question,
+                Mark::None,
+            ]
+        );
+    }
+
+    #[test]
+    fn mines_remaining_subtracts_what_each_flag_claims() {
+        let mut board = layout(&["3.", ".2"]);
+        assert_eq!(board.mines_remaining(), 5);
+        board.toggle_flag(0, 0);
+        board.toggle_flag(0, 0);
+        board.toggle_flag(1, 1);
+        assert_eq!(board.mines_remaining(), 2);
+    }
+
+    #[test]
+    fn chording_needs_the_flags_to_claim_the_whole_number() {
+        let mut board = layout(&["2..", "...", "..."]);
+        board.flood(1, 1);
+        board.toggle_flag(0, 0);
+        assert_eq!(board.chord(1, 1), None);
+        assert!(!board.cell(2, 2).uncovered, "one flag claims one of two");
+        board.toggle_flag(0, 0);
+        assert_eq!(board.chord(1, 1), Some(GameResult::Won));
+    }
+
+    #[test]
+    fn revealing_a_stack_loses_like_any_mine() {
+        let mut board = layout(&["2.", ".."]);
+        assert_eq!(board.reveal(0, 0, &mut rng()), Some(GameResult::Lost));
+        assert_eq!(board.cell(0, 0).content, Cell::Mine(2));
+        assert!(board.cell(0, 0).uncovered);
+    }
+
+    #[test]
+    fn seeding_lays_every_mine_in_stacks_no_taller_than_allowed() {
+        let config = Config::new(4, 4, 0)
+            .expect("valid config")
+            .with_mines_per_cell(3);
+        let config = Config {
+            mines: 21,
+            ..config
+        }
+        .check()
+        .expect("maximum stacked density");
+        for seed in 0..200 {
+            let mut board = Board::new(config.clone());
+            assert_ne!(
+                board.reveal(1, 1, &mut Generator::new(seed)),
+                Some(GameResult::Lost)
+            );
+            let stacks: Vec<usize> = board.rows().flatten().map(|c| c.content.mines()).collect();
+            assert_eq!(stacks.iter().sum::<usize>(), 21);
+            assert!(stacks.iter().all(|&count| count <= 3));
+        }
+    }
+}
+
+/// Games that survive a mine hit or more.
+#[cfg(test)]
+mod lives_tests {
+    use super::fixtures::{layout_with, rng};
+    use super::*;
+
+    fn lives(rows: &[&str], lives: usize) -> Board {
+        layout_with(rows, |config| config.with_lives(lives))
+    }
+
+    #[test]
+    fn a_hit_with_a_life_to_spare_leaves_only_that_mine_showing() {
+        let mut board = lives(&["*..", "...", "..*"], 2);
+        assert_eq!(board.lives_remaining(), 2);
+        assert_eq!(board.reveal(0, 0, &mut rng()), None);
+        assert!(board.cell(0, 0).is_exploded());
+        assert!(!board.cell(2, 2).uncovered);
+        assert_eq!(board.lives_remaining(), 1);
+        assert_eq!(board.mines_remaining(), 1);
+    }
+
+    #[test]
+    fn the_hit_that_spends_the_last_life_loses() {
+        let mut board = lives(&["*..", "...", "..*"], 2);
+        board.reveal(0, 0, &mut rng());
+        assert_eq!(board.reveal(2, 2, &mut rng()), Some(GameResult::Lost));
+        assert_eq!(board.lives_remaining(), 0);
+    }
+
+    #[test]
+    fn the_game_can_still_be_won_after_a_hit() {
+        let mut board = lives(&["*..", "...", "..."], 2);
+        board.reveal(0, 0, &mut rng());
+        assert_eq!(board.reveal(2, 2, &mut rng()), Some(GameResult::Won));
+    }
+
+    #[test]
+    fn an_exploded_mine_counts_as_a_flag_when_chording() {
+        let mut board = lives(&["*..", "...", "..."], 2);
+        board.reveal(0, 0, &mut rng());
+        board.flood(1, 1);
+        assert_eq!(board.chord(1, 1), Some(GameResult::Won));
+    }
+
+    #[test]
+    fn a_chord_past_a_misplaced_flag_costs_a_life_and_opens_the_rest() {
+        let mut board = lives(&["*..", "...", "..."], 2);
+        board.flood(1, 1);
+        board.toggle_flag(0, 1);
+        assert_eq!(board.chord(1, 1), None);
+        assert!(board.cell(0, 0).is_exploded());
+        assert!(board.cell(2, 2).uncovered);
+        assert_eq!(board.lives_remaining(), 1);
+    }
+
+    #[test]
+    fn a_chord_that_spends_the_last_life_opens_nothing_safe() {
+        let mut board = lives(&["*..", "...", "..."], 1);
+        board.flood(1, 1);
+        board.toggle_flag(0, 1);
+        assert_eq!(board.chord(1, 1), Some(GameResult::Lost));
+        assert!(!board.cell(2, 2).uncovered);
+    }
+
+    #[test]
+    fn the_game_state_plays_on_until_the_lives_run_out() {
+        let state = GameState {
+            board: lives(&["*..", "...", "..*"], 2),
+            result: None,
+        };
+        let state = state.apply(Action::Reveal(0, 0), &mut rng());
+        assert!(!state.is_over());
+        let state = state.apply(Action::Reveal(2, 2), &mut rng());
+        assert_eq!(state.result, Some(GameResult::Lost));
+    }
+}
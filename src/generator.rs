@@ -0,0 +1,277 @@
+//! Portable mine layouts.
+//!
+//! A seed or a replay shared today has to draw the same board after any
+//! dependency upgrade and on any platform, which `rand` does not promise for
+//! `StdRng` or `choose_multiple`. So the layout draw is specified here, in
+//! full, and frozen under a [`Version`]: for a given version, seed, config and
+//! first click, [`Board`](crate::game::Board) lays the same mines forever.
+//! The version covers the whole layout, not only the stream: `Board::seed`
+//! matches on it before reserving the first click's cells, drawing,
+//! relocating a mine from under the click and asking the solver whether a
+//! no-guess draw needs a redraw. Changing any of those steps, down to the
+//! order of two calls or the strength of the solver, is a new version; the
+//! golden tests at the bottom fail until it is one.
+//!
+//! Version 1 seeds xoshiro256** from SplitMix64, draws bounded integers by
+//! rejection, and picks mines with a partial Fisher-Yates shuffle over the
+//! unreserved cells in row-major order. A mine under a relocating click moves
+//! to the first cell in row-major order with room for it, and a no-guess
+//! board redraws until the solver's version 1 rules clear it. All arithmetic
+//! is on `u64`, so a 32-bit `usize` on wasm changes nothing.
+
+/// A frozen layout algorithm. Saved with every replay so an old recording
+/// keeps drawing its boards the old way once a newer version exists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+    V1,
+}
+
+impl Version {
+    /// What new games are drawn with.
+    pub const LATEST: Version = Version::V1;
+
+    pub fn number(self) -> u32 {
+        match self {
+            Version::V1 => 1,
+        }
+    }
+
+    pub fn from_number(number: u32) -> Option<Self> {
+        match number {
+            1 => Some(Version::V1),
+            _ => None,
+        }
+    }
+}
+
+/// The only source of randomness the rules use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Generator {
+    version: Version,
+    state: [u64; 4],
+}
+
+impl Generator {
+    /// A generator of the [latest](Version::LATEST) version.
+    pub fn new(seed: u64) -> Self {
+        Self::with_version(Version::LATEST, seed)
+    }
+
+    /// Expands `seed` into the xoshiro state with four SplitMix64 outputs, as
+    /// the xoshiro authors recommend; no seed leaves the state all zero.
+    pub fn with_version(version: Version, seed: u64) -> Self {
+        let mut splitmix = seed;
+        let state = [(); 4].map(|()| {
+            splitmix = splitmix.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = splitmix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^ (z >> 31)
+        });
+        Self { version, state }
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// The next xoshiro256** output.
+    pub fn next_u64(&mut self) -> u64 {
+        let [s0, s1, s2, s3] = &mut self.state;
+        let result = s1.wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = *s1 << 17;
+        *s2 ^= *s0;
+        *s3 ^= *s1;
+        *s1 ^= *s2;
+        *s0 ^= *s3;
+        *s2 ^= t;
+        *s3 = s3.rotate_left(45);
+        result
+    }
+
+    /// Uniform in `0..bound`. Outputs from the top partial multiple of
+    /// `bound` are thrown away and redrawn, so no value is favoured.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "cannot draw from an empty range");
+        let limit = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < limit {
+                return value % bound;
+            }
+        }
+    }
+
+    /// `amount` entries of `candidates` at distinct positions, in the order
+    /// drawn: a partial Fisher-Yates shuffle that stops after `amount` swaps.
+    /// Asking for more than there are returns them all, shuffled.
+    pub fn choose(&mut self, candidates: &[usize], amount: usize) -> Vec<usize> {
+        let mut pool = candidates.to_vec();
+        let amount = amount.min(pool.len());
+        for i in 0..amount {
+            let left = (pool.len() - i) as u64;
+            let j = i + self.below(left) as usize;
+            pool.swap(i, j);
+        }
+        pool.truncate(amount);
+        pool
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, FirstClick, Generation, Grid};
+    use crate::game::Board;
+
+    /// The published xoshiro256** sequence from the state `[1, 2, 3, 4]`.
+    #[test]
+    fn the_core_matches_the_reference_xoshiro256_star_star() {
+        let mut generator = Generator {
+            version: Version::V1,
+            state: [1, 2, 3, 4],
+        };
+        let outputs = [(); 4].map(|()| generator.next_u64());
+        assert_eq!(outputs, [11520, 0, 1509978240, 1215971899390074240]);
+    }
+
+    #[test]
+    fn version_one_outputs_are_frozen() {
+        let mut generator = Generator::with_version(Version::V1, 0);
+        let outputs = [(); 3].map(|()| generator.next_u64());
+        assert_eq!(outputs, GOLDEN_OUTPUTS);
+    }
+
+    #[test]
+    fn a_bounded_draw_stays_in_range_and_reaches_every_value() {
+        let mut generator = Generator::new(7);
+        let mut seen = [false; 6];
+        for _ in 0..600 {
+            seen[generator.below(6) as usize] = true;
+        }
+        assert!(seen.iter().all(|&hit| hit));
+    }
+
+    #[test]
+    fn choosing_picks_distinct_positions() {
+        let mut generator = Generator::new(3);
+        let mut chosen = generator.choose(&(0..20).collect::<Vec<_>>(), 20);
+        chosen.sort_unstable();
+        assert_eq!(chosen, (0..20).collect::<Vec<_>>());
+        assert_eq!(generator.choose(&[4, 5], 9).len(), 2);
+    }
+
+    /// The mines as a picture, `*` for a mine and `.` for anything else.
+    fn layout(config: Config, click: (usize, usize), seed: u64) -> Vec<String> {
+        let mut board = Board::new(config);
+        board.reveal(
+            click.0,
+            click.1,
+            &mut Generator::with_version(Version::V1, seed),
+        );
+        board
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell.content.is_mine() {
+                        true => '*',
+                        false => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn version_one_layouts_are_frozen() {
+        let beginner = Config::new(9, 9, 10).expect("valid config");
+        assert_eq!(layout(beginner.clone(), (4, 4), 1), GOLDEN_BEGINNER);
+        assert_eq!(
+            layout(beginner.with_first_click(FirstClick::Relocate), (0, 0), 3),
+            GOLDEN_RELOCATE
+        );
+        let hex = Config::new(8, 6, 12)
+            .expect("valid config")
+            .with_grid(Grid::Hex);
+        assert_eq!(layout(hex.clone(), (5, 7), 3), GOLDEN_HEX);
+        let hex = hex.with_first_click(FirstClick::Relocate);
+        assert_eq!(layout(hex, (2, 3), 1), GOLDEN_HEX_RELOCATE);
+        let cube = Config::new(5, 4, 0)
+            .expect("valid config")
+            .with_depth(2)
+            .with_first_click(FirstClick::Relocate);
+        let cube = Config { mines: 12, ..cube };
+        assert_eq!(layout(cube, (5, 2), 3), GOLDEN_CUBE_RELOCATE);
+    }
+
+    /// Seed 0's first draw needs a guess, so this pins the redraw and the
+    /// solver that judged it as well as the stream.
+    #[test]
+    fn version_one_no_guess_layouts_are_frozen() {
+        let beginner = Config::new(9, 9, 10)
+            .expect("valid config")
+            .with_generation(Generation::NoGuess);
+        assert_ne!(
+            layout(
+                beginner.clone().with_generation(Generation::Uniform),
+                (4, 4),
+                0
+            ),
+            GOLDEN_NO_GUESS
+        );
+        assert_eq!(layout(beginner, (4, 4), 0), GOLDEN_NO_GUESS);
+    }
+
+    const GOLDEN_OUTPUTS: [u64; 3] = [
+        11091344671253066420,
+        13793997310169335082,
+        1900383378846508768,
+    ];
+    const GOLDEN_BEGINNER: [&str; 9] = [
+        ".......*.",
+        "....*.*..",
+        ".....*...",
+        ".......*.",
+        "........*",
+        "..*......",
+        ".........",
+        "....**..*",
+        ".........",
+    ];
+    /// Seed 3 lays a mine under the click, which moves one cell right.
+    const GOLDEN_RELOCATE: [&str; 9] = [
+        ".*....*..",
+        ".....*...",
+        ".........",
+        "..*.*....",
+        ".*.....*.",
+        ".........",
+        ".........",
+        "....*....",
+        "*...*....",
+    ];
+    const GOLDEN_HEX: [&str; 6] = [
+        ".*......", "...*.*..", ".*..*...", ".*.*.*.*", "*..*..*.", "........",
+    ];
+    /// Seed 1 lays a mine under the click; the first free cell in row-major
+    /// order takes it.
+    const GOLDEN_HEX_RELOCATE: [&str; 6] = [
+        "**..*...", ".*.*....", "..*.*...", ".*..*..*", ".*...*..", "........",
+    ];
+    /// Two layers of four rows. Seed 3 lays a mine under the click in the
+    /// second, which moves to the first free cell of the first.
+    const GOLDEN_CUBE_RELOCATE: [&str; 8] = [
+        "**...", "*.**.", "..*.*", "*..*.", "..*..", ".....", "*..*.", ".....",
+    ];
+    const GOLDEN_NO_GUESS: [&str; 9] = [
+        ".........",
+        "..**...*.",
+        ".*.**....",
+        "*........",
+        ".........",
+        "........*",
+        "......*..",
+        ".........",
+        "...*.....",
+    ];
+}
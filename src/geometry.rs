@@ -0,0 +1,239 @@
+//! Which cells touch which.
+//!
+//! The grid shape, kernel and topology in a [`Config`] decide every
+//! neighbourhood on the board. Counting, flood fill, the first-click reserve,
+//! the solver and `Config`'s own capacity check all ask [`neighbors`], so a
+//! new shape only has to be taught here.
+//!
+//! Hex boards use "odd-r" offset rows: every odd row sits half a cell to the
+//! right, so a cell touches two cells in each of the rows above and below it,
+//! shifted towards the side its row leans.
+//!
+//! A cube is a stack of such layers, stored one after another as extra rows,
+//! so the layer is `row / height`. Under the Moore kernel, up to 26 cells touch
+//! a square cell in one.
+
+use crate::config::{Config, Grid, Kernel};
+
+/// The eight steps of a square cell under the Moore kernel.
+const SQUARE: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+const ORTHOGONAL: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+const KNIGHT: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+const RADIUS_2: [(isize, isize); 24] = square_block();
+
+/// Every step within two rows and columns, in row-major order.
+const fn square_block() -> [(isize, isize); 24] {
+    let mut steps = [(0, 0); 24];
+    let (mut dr, mut len) = (-2, 0);
+    while dr <= 2 {
+        let mut dc = -2;
+        while dc <= 2 {
+            if dr != 0 || dc != 0 {
+                steps[len] = (dr, dc);
+                len += 1;
+            }
+            dc += 1;
+        }
+        dr += 1;
+    }
+    steps
+}
+
+/// The six steps of a hex cell on an even row, which leans left.
+const HEX_EVEN: [(isize, isize); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+
+/// The six steps of a hex cell on an odd row, which leans right.
+const HEX_ODD: [(isize, isize); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+
+/// The distinct cells touching `(row, col)`, excluding the cell itself. Past a
+/// flat edge there is nothing; past a joined one the walk comes back in on the
+/// far side. On a board two cells across, both ways round lead to the same
+/// cell, which is listed once. A custom kernel need not be symmetric, so
+/// neither is this: `(row, col)` touches the cells at its own offsets. A gap
+/// in the mask touches nothing and is nobody's neighbour. In a cube, a cell
+/// also touches the cells directly above and below it and their neighbours
+/// within their layers; layers never wrap.
+pub fn neighbors(config: &Config, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> {
+    if !config.contains(row, col) {
+        return Vec::new().into_iter();
+    }
+    let (layer, row_in_layer) = (row / config.height, row % config.height);
+    let custom: Vec<_>;
+    let steps: &[(isize, isize)] = match (config.grid, config.kernel, row_in_layer % 2) {
+        (Grid::Hex, _, 0) => &HEX_EVEN,
+        (Grid::Hex, _, _) => &HEX_ODD,
+        (Grid::Square, Kernel::Moore, _) => &SQUARE,
+        (Grid::Square, Kernel::Orthogonal, _) => &ORTHOGONAL,
+        (Grid::Square, Kernel::Knight, _) => &KNIGHT,
+        (Grid::Square, Kernel::Radius2, _) => &RADIUS_2,
+        (Grid::Square, Kernel::Custom(offsets), _) => {
+            custom = offsets.iter().collect();
+            &custom
+        }
+    };
+    let mut found = Vec::with_capacity(3 * (steps.len() + 1));
+    for dl in -1..=1 {
+        let Some(other) = offset(layer, dl, config.depth, false) else {
+            continue;
+        };
+        let beside = steps
+            .iter()
+            .filter_map(|&(dr, dc)| step(config, row_in_layer, col, dr, dc));
+        for (r, c) in std::iter::once((row_in_layer, col)).chain(beside) {
+            let position = (other * config.height + r, c);
+            if position != (row, col)
+                && config.contains(position.0, position.1)
+                && !found.contains(&position)
+            {
+                found.push(position);
+            }
+        }
+    }
+    found.into_iter()
+}
+
+/// The most cells any first click keeps clear: itself and its neighbours.
+pub fn safe_region(config: &Config) -> usize {
+    config
+        .positions()
+        .map(|(row, col)| 1 + neighbors(config, row, col).count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// The cell `dr` rows and `dc` columns away within one layer, through the
+/// topology.
+fn step(config: &Config, row: usize, col: usize, dr: isize, dc: isize) -> Option<(usize, usize)> {
+    let topology = config.topology;
+    Some((
+        offset(row, dr, config.height, topology.wraps_rows())?,
+        offset(col, dc, config.width, topology.wraps_columns())?,
+    ))
+}
+
+/// `index + delta` along an axis of `size` cells, wrapping round if `wraps`.
+fn offset(index: usize, delta: isize, size: usize, wraps: bool) -> Option<usize> {
+    let moved = index as isize + delta;
+    match wraps {
+        true => Some(moved.rem_euclid(size as isize) as usize),
+        false => (0..size as isize)
+            .contains(&moved)
+            .then_some(moved as usize),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Mask, Offsets, Topology};
+
+    fn hex(width: usize, height: usize) -> Config {
+        Config::new(width, height, 0)
+            .expect("valid config")
+            .with_grid(Grid::Hex)
+    }
+
+    #[test]
+    fn an_inner_hex_cell_has_six_neighbors_leaning_with_its_row() {
+        let config = hex(5, 5);
+        let mut even: Vec<_> = neighbors(&config, 2, 2).collect();
+        even.sort_unstable();
+        assert_eq!(even, [(1, 1), (1, 2), (2, 1), (2, 3), (3, 1), (3, 2)]);
+        let mut odd: Vec<_> = neighbors(&config, 1, 2).collect();
+        odd.sort_unstable();
+        assert_eq!(odd, [(0, 2), (0, 3), (1, 1), (1, 3), (2, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn hex_adjacency_is_symmetric_even_across_a_torus_wrap() {
+        for config in [hex(5, 4), hex(4, 6).with_topology(Topology::Torus)] {
+            for row in 0..config.height {
+                for col in 0..config.width {
+                    for (r, c) in neighbors(&config, row, col) {
+                        assert!(
+                            neighbors(&config, r, c).any(|p| p == (row, col)),
+                            "({row}, {col}) touches ({r}, {c}) but not the reverse"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_cube_cell_touches_the_layers_either_side() {
+        let cube = Config::new(3, 3, 0).expect("valid config").with_depth(3);
+        assert_eq!(neighbors(&cube, 4, 1).count(), 26);
+        assert_eq!(neighbors(&cube, 0, 0).count(), 7);
+        // The top layer's centre touches the middle layer but not the bottom.
+        assert!(neighbors(&cube, 1, 1).any(|p| p == (4, 1)));
+        assert!(!neighbors(&cube, 1, 1).any(|p| p == (7, 1)));
+        let hex = cube.with_grid(Grid::Hex);
+        assert_eq!(neighbors(&hex, 4, 1).count(), 20);
+    }
+
+    #[test]
+    fn a_hex_first_click_reserves_seven_cells() {
+        assert_eq!(safe_region(&hex(9, 9)), 7);
+        assert_eq!(safe_region(&Config::new(9, 9, 0).expect("valid config")), 9);
+    }
+
+    #[test]
+    fn a_knight_touches_the_cells_a_knight_could_jump_to() {
+        let knight = Config::new(5, 5, 0)
+            .expect("valid config")
+            .with_kernel(Kernel::Knight);
+        assert_eq!(neighbors(&knight, 2, 2).count(), 8);
+        let mut corner: Vec<_> = neighbors(&knight, 0, 0).collect();
+        corner.sort_unstable();
+        assert_eq!(corner, [(1, 2), (2, 1)]);
+        let torus = knight.with_topology(Topology::Torus);
+        assert_eq!(neighbors(&torus, 0, 0).count(), 8);
+    }
+
+    #[test]
+    fn a_custom_kernel_reaches_only_its_own_offsets() {
+        let offsets = Offsets::new(&[(0, 1), (0, 4)]).expect("valid offsets");
+        let config = Config::new(9, 1, 0)
+            .expect("valid config")
+            .with_kernel(Kernel::Custom(offsets));
+        assert_eq!(
+            neighbors(&config, 0, 4).collect::<Vec<_>>(),
+            [(0, 5), (0, 8)]
+        );
+        assert_eq!(neighbors(&config, 0, 5).collect::<Vec<_>>(), [(0, 6)]);
+        assert_eq!(safe_region(&config), 3);
+    }
+
+    #[test]
+    fn a_gap_is_nobodys_neighbor() {
+        let mask = Mask::from_rows(&["###", "#-#", "###"]).expect("valid mask");
+        let ring = Config::new(3, 3, 0)
+            .expect("valid config")
+            .with_mask(Some(mask));
+        assert_eq!(neighbors(&ring, 0, 0).count(), 2);
+        assert!(!neighbors(&ring, 0, 1).any(|p| p == (1, 1)));
+        assert_eq!(safe_region(&ring), 5);
+    }
+}
@@ -0,0 +1,268 @@
+//! Undo and redo over [`GameState`] transitions.
+//!
+//! `GameState::apply` is pure, so history is nothing more than the states it
+//! has returned: a stack behind the present and a stack ahead of it. Both
+//! stacks are persistent, so every move shares the states before it instead
+//! of copying them, and a `History` clones in constant time however long the
+//! game has run.
+
+use std::fmt;
+use std::rc::Rc;
+
+use crate::config::{Config, UndoPolicy};
+use crate::game::{Action, GameResult, GameState};
+use crate::generator::Generator;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct History {
+    past: Stack,
+    present: GameState,
+    future: Stack,
+}
+
+/// A linked stack whose tails are shared: pushing builds one node on top of
+/// the old stack, which every earlier `History` keeps seeing unchanged.
+#[derive(Clone, Default)]
+struct Stack(Option<Rc<(GameState, Stack)>>);
+
+impl Stack {
+    fn push(&self, state: GameState) -> Self {
+        Stack(Some(Rc::new((state, self.clone()))))
+    }
+
+    /// The top state and the stack beneath it.
+    fn pop(&self) -> Option<(GameState, Stack)> {
+        self.0
+            .as_deref()
+            .map(|(state, rest)| (state.clone(), rest.clone()))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// The nodes from the top down, each with the stack beneath it.
+    fn nodes(&self) -> impl Iterator<Item = &(GameState, Stack)> {
+        std::iter::successors(self.0.as_deref(), |(_, rest)| rest.0.as_deref())
+    }
+}
+
+/// Walks both stacks side by side, and stops early where they share a tail,
+/// rather than recursing once per move as a derived comparison would.
+impl PartialEq for Stack {
+    fn eq(&self, other: &Self) -> bool {
+        let (mut ours, mut theirs) = (self, other);
+        loop {
+            match (&ours.0, &theirs.0) {
+                (None, None) => return true,
+                (Some(a), Some(b)) if Rc::ptr_eq(a, b) => return true,
+                (Some(a), Some(b)) if a.0 == b.0 => (ours, theirs) = (&a.1, &b.1),
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl Eq for Stack {}
+
+/// Only the depth: a long game's every state would be unreadable anyway.
+impl fmt::Debug for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} states", self.nodes().count())
+    }
+}
+
+/// Unlinks the nodes nobody else holds one at a time, so a game of any length
+/// drops without recursing once per move.
+impl Drop for Stack {
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(node) = next {
+            next = match Rc::try_unwrap(node) {
+                Ok((_, mut rest)) => rest.0.take(),
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+impl From<GameState> for History {
+    fn from(present: GameState) -> Self {
+        Self {
+            past: Stack::default(),
+            present,
+            future: Stack::default(),
+        }
+    }
+}
+
+impl History {
+    pub fn new(config: Config) -> Self {
+        GameState::new(config).into()
+    }
+
+    pub fn present(&self) -> &GameState {
+        &self.present
+    }
+
+    /// False with nothing to go back to, or when the config makes a loss final
+    /// and the present is one.
+    pub fn can_undo(&self) -> bool {
+        let final_loss = self.present.result == Some(GameResult::Lost)
+            && self.present.board.config().undo == UndoPolicy::LossIsFinal;
+        !self.past.is_empty() && !final_loss
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.future.is_empty()
+    }
+
+    /// Steps through history on `Undo` and `Redo`, starts afresh on `Restart`,
+    /// and otherwise records the transition. An action that changes nothing
+    /// leaves no entry to undo.
+    pub fn apply(&self, action: Action, rng: &mut Generator) -> Self {
+        match action {
+            Action::Undo => self.undone(),
+            Action::Redo => self.redone(),
+            Action::Restart => self.present.apply(action, rng).into(),
+            _ => self.played(action, rng),
+        }
+    }
+
+    fn played(&self, action: Action, rng: &mut Generator) -> Self {
+        let next = self.present.apply(action, rng);
+        if next == self.present {
+            return self.clone();
+        }
+        Self {
+            past: self.past.push(self.present.clone()),
+            present: next,
+            future: Stack::default(),
+        }
+    }
+
+    fn undone(&self) -> Self {
+        if !self.can_undo() {
+            return self.clone();
+        }
+        let (previous, past) = self.past.pop().expect("can_undo checked the past");
+        Self {
+            past,
+            present: previous,
+            future: self.future.push(self.present.clone()),
+        }
+    }
+
+    fn redone(&self) -> Self {
+        let Some((following, future)) = self.future.pop() else {
+            return self.clone();
+        };
+        Self {
+            past: self.past.push(self.present.clone()),
+            present: following,
+            future,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::{layout_with, rng};
+
+    fn history(rows: &[&str], undo: UndoPolicy) -> History {
+        GameState {
+            board: layout_with(rows, |config| config.with_undo(undo)),
+            result: None,
+        }
+        .into()
+    }
+
+    #[test]
+    fn undo_steps_back_one_action_and_redo_steps_forward_again() {
+        let start = history(&["*...", "....", "...*"], UndoPolicy::LossIsFinal);
+        let flagged = start.apply(Action::Flag(0, 0), &mut rng());
+        let undone = flagged.apply(Action::Undo, &mut rng());
+        assert_eq!(undone.present(), start.present());
+        assert!(undone.can_redo());
+        let redone = undone.apply(Action::Redo, &mut rng());
+        assert_eq!(redone.present(), flagged.present());
+    }
+
+    #[test]
+    fn a_new_action_after_an_undo_discards_the_redo_stack() {
+        let start = history(&["*...", "....", "...*"], UndoPolicy::LossIsFinal);
+        let branched = start
+            .apply(Action::Flag(0, 0), &mut rng())
+            .apply(Action::Undo, &mut rng())
+            .apply(Action::Flag(2, 3), &mut rng());
+        assert!(!branched.can_redo());
+        assert_eq!(branched.apply(Action::Redo, &mut rng()), branched);
+    }
+
+    #[test]
+    fn an_action_that_changes_nothing_leaves_no_entry_to_undo() {
+        let start = history(&["*...", "....", "...*"], UndoPolicy::LossIsFinal);
+        let ignored = start.apply(Action::Reveal(9, 9), &mut rng());
+        assert!(!ignored.can_undo());
+    }
+
+    #[test]
+    fn undo_and_redo_at_either_end_of_history_are_no_ops() {
+        let start = history(&["*...", "....", "...*"], UndoPolicy::LossIsFinal);
+        assert_eq!(start.apply(Action::Undo, &mut rng()), start);
+        assert_eq!(start.apply(Action::Redo, &mut rng()), start);
+    }
+
+    #[test]
+    fn by_default_a_loss_cannot_be_undone() {
+        let start = history(&["*...", "....", "...*"], UndoPolicy::LossIsFinal);
+        let lost = start.apply(Action::Reveal(0, 0), &mut rng());
+        assert!(lost.present().is_over());
+        assert!(!lost.can_undo());
+        assert_eq!(lost.apply(Action::Undo, &mut rng()), lost);
+    }
+
+    #[test]
+    fn a_permissive_policy_takes_the_losing_reveal_back() {
+        let start = history(&["*...", "....", "...*"], UndoPolicy::AllowAfterLoss);
+        let lost = start.apply(Action::Reveal(0, 0), &mut rng());
+        let undone = lost.apply(Action::Undo, &mut rng());
+        assert_eq!(undone.present(), start.present());
+        assert!(!undone.present().is_over());
+    }
+
+    #[test]
+    fn a_long_game_undoes_to_its_start_and_drops_without_overflowing() {
+        let start = history(&["*...", "....", "...*"], UndoPolicy::LossIsFinal);
+        let moves = 100_000;
+        let play = || {
+            (0..moves).fold(start.clone(), |history, _| {
+                history.apply(Action::Flag(0, 0), &mut rng())
+            })
+        };
+        let played = play();
+        let mut undone = played.clone();
+        for _ in 0..moves {
+            undone = undone.apply(Action::Undo, &mut rng());
+        }
+        assert_eq!(undone.present(), start.present());
+        assert!(!undone.can_undo());
+        // Built apart, so comparing them walks every move.
+        assert_eq!(played, play());
+        assert!(format!("{played:?}").contains("past: 100000 states"));
+        drop((played, undone));
+    }
+
+    #[test]
+    fn restart_clears_history_in_both_directions() {
+        let start = history(&["*...", "....", "...*"], UndoPolicy::LossIsFinal);
+        let restarted = start
+            .apply(Action::Flag(0, 0), &mut rng())
+            .apply(Action::Flag(2, 3), &mut rng())
+            .apply(Action::Undo, &mut rng())
+            .apply(Action::Restart, &mut rng());
+        assert!(!restarted.can_undo());
+        assert!(!restarted.can_redo());
+    }
+}
@@ -0,0 +1,365 @@
+//! The endless board: an unbounded plane of cells, laid out lazily.
+//!
+//! The plane is cut into square chunks [`CHUNK`] cells a side. A chunk's mines
+//! are drawn from the world seed and the chunk's own coordinates the first
+//! time anything looks at it, so the whole layout is fixed from the start,
+//! yet only the chunks a player has reached, and the ring around them that
+//! their numbers count, ever exist. There is no win: the score is the number
+//! of safe cells uncovered before the first mine.
+
+use std::collections::HashMap;
+
+use crate::config::{check_density, ConfigError};
+use crate::generator::{Generator, Version};
+
+/// Cells along each side of a chunk.
+pub const CHUNK: i64 = 16;
+
+/// Percent chance of a mine is drawn against this.
+const PERCENT: u64 = 100;
+
+/// What the player sees of one cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tile {
+    Covered,
+    Flagged,
+    /// Uncovered and safe, with the number of mines around it.
+    Open(u8),
+    /// The mine that ended the game.
+    Exploded,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Chunk {
+    mines: Vec<bool>,
+    tiles: Vec<Tile>,
+}
+
+impl Chunk {
+    /// Each cell is a mine with `density` percent chance, drawn in row-major
+    /// order from a generator seeded by the chunk's place in the world.
+    fn draw(version: Version, seed: u64, density: usize, (row, col): (i64, i64)) -> Self {
+        let seed = seed
+            ^ (row as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (col as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+        let mut generator = Generator::with_version(version, seed);
+        let area = (CHUNK * CHUNK) as usize;
+        Self {
+            mines: (0..area)
+                .map(|_| generator.below(PERCENT) < density as u64)
+                .collect(),
+            tiles: vec![Tile::Covered; area],
+        }
+    }
+}
+
+/// The chunk a cell lies in and its index there. Euclidean division keeps
+/// the chunks square on the negative side of zero too.
+fn locate(row: i64, col: i64) -> ((i64, i64), usize) {
+    let key = (row.div_euclid(CHUNK), col.div_euclid(CHUNK));
+    (
+        key,
+        (row.rem_euclid(CHUNK) * CHUNK + col.rem_euclid(CHUNK)) as usize,
+    )
+}
+
+fn neighbours(row: i64, col: i64) -> impl Iterator<Item = (i64, i64)> {
+    (-1..=1)
+        .flat_map(move |dr| (-1..=1).map(move |dc| (row + dr, col + dc)))
+        .filter(move |&cell| cell != (row, col))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct World {
+    version: Version,
+    seed: u64,
+    density: usize,
+    chunks: HashMap<(i64, i64), Chunk>,
+    /// The first reveal. It and its neighbours are never mines, so the game
+    /// starts with an opening; until then no count has been shown that this
+    /// could contradict.
+    opening: Option<(i64, i64)>,
+    score: u64,
+    over: bool,
+}
+
+impl World {
+    /// A world of the [latest](Version::LATEST) generator with `density`
+    /// percent of its cells mined.
+    pub fn new(seed: u64, density: usize) -> Result<Self, ConfigError> {
+        Ok(Self {
+            version: Version::LATEST,
+            seed,
+            density: check_density(density)?,
+            chunks: HashMap::new(),
+            opening: None,
+            score: 0,
+            over: false,
+        })
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn density(&self) -> usize {
+        self.density
+    }
+
+    /// Safe cells uncovered so far.
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// How many chunks have been drawn.
+    pub fn chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Anywhere nobody has looked yet is simply covered.
+    pub fn tile(&self, row: i64, col: i64) -> Tile {
+        let (key, index) = locate(row, col);
+        self.chunks
+            .get(&key)
+            .map_or(Tile::Covered, |chunk| chunk.tiles[index])
+    }
+
+    pub fn flag(&mut self, row: i64, col: i64) {
+        let tile = match self.tile(row, col) {
+            _ if self.over => return,
+            Tile::Covered => Tile::Flagged,
+            Tile::Flagged => Tile::Covered,
+            _ => return,
+        };
+        self.set(row, col, tile);
+    }
+
+    /// Uncovers a covered cell; a blank floods outward, across chunk edges,
+    /// until numbers fence it in.
+    pub fn reveal(&mut self, row: i64, col: i64) {
+        if self.over || self.tile(row, col) != Tile::Covered {
+            return;
+        }
+        self.opening.get_or_insert((row, col));
+        if self.is_mine(row, col) {
+            self.set(row, col, Tile::Exploded);
+            self.over = true;
+            return;
+        }
+        let mut pending = vec![(row, col)];
+        while let Some((row, col)) = pending.pop() {
+            if self.tile(row, col) != Tile::Covered {
+                continue;
+            }
+            let count = self.count(row, col);
+            self.set(row, col, Tile::Open(count));
+            self.score += 1;
+            if count == 0 {
+                pending.extend(neighbours(row, col));
+            }
+        }
+    }
+
+    /// Uncovers the unflagged neighbours of a number whose flags add up.
+    pub fn chord(&mut self, row: i64, col: i64) {
+        let Tile::Open(count) = self.tile(row, col) else {
+            return;
+        };
+        let flags = neighbours(row, col)
+            .filter(|&(row, col)| self.tile(row, col) == Tile::Flagged)
+            .count();
+        if count == 0 || flags != usize::from(count) {
+            return;
+        }
+        for (row, col) in neighbours(row, col) {
+            self.reveal(row, col);
+        }
+    }
+
+    fn set(&mut self, row: i64, col: i64, tile: Tile) {
+        let (key, index) = locate(row, col);
+        self.chunk(key).tiles[index] = tile;
+    }
+
+    fn chunk(&mut self, key: (i64, i64)) -> &mut Chunk {
+        let (version, seed, density) = (self.version, self.seed, self.density);
+        self.chunks
+            .entry(key)
+            .or_insert_with(|| Chunk::draw(version, seed, density, key))
+    }
+
+    fn is_mine(&mut self, row: i64, col: i64) -> bool {
+        if let Some((first_row, first_col)) = self.opening {
+            if (row - first_row).abs() <= 1 && (col - first_col).abs() <= 1 {
+                return false;
+            }
+        }
+        let (key, index) = locate(row, col);
+        self.chunk(key).mines[index]
+    }
+
+    fn count(&mut self, row: i64, col: i64) -> u8 {
+        neighbours(row, col)
+            .filter(|&(row, col)| self.is_mine(row, col))
+            .count() as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(seed: u64) -> World {
+        World::new(seed, 18).expect("valid density")
+    }
+
+    /// A mine and a safe cell far from the origin, found by looking.
+    fn find(world: &mut World, mine: bool) -> (i64, i64) {
+        (0..CHUNK * 4)
+            .map(|col| (-40, col))
+            .find(|&(row, col)| world.is_mine(row, col) == mine)
+            .expect("a chunk row holds both")
+    }
+
+    #[test]
+    fn the_layout_does_not_depend_on_the_order_chunks_are_drawn() {
+        let (mut a, mut b) = (world(5), world(5));
+        let far = (CHUNK * 7 + 3, -CHUNK * 2 - 5);
+        let near = (4, 9);
+        let here = a.is_mine(far.0, far.1);
+        let there = a.is_mine(near.0, near.1);
+        assert_eq!(
+            (b.is_mine(near.0, near.1), b.is_mine(far.0, far.1)),
+            (there, here)
+        );
+        let picture = |world: &mut World| -> Vec<bool> {
+            (-20..20)
+                .flat_map(|row| (-20..20).map(move |col| (row, col)))
+                .map(|(row, col)| world.is_mine(row, col))
+                .collect()
+        };
+        assert_eq!(picture(&mut a), picture(&mut b));
+        assert_ne!(picture(&mut a), picture(&mut world(6)));
+    }
+
+    #[test]
+    fn the_density_is_roughly_what_was_asked_for_and_checked() {
+        let mut world = world(1);
+        let cells = 200 * 200;
+        let mines = (0..200)
+            .flat_map(|row| (0..200).map(move |col| (row, col)))
+            .filter(|&(row, col)| world.is_mine(row, col))
+            .count();
+        assert!((cells * 15 / 100..cells * 21 / 100).contains(&mines));
+        assert_eq!(World::new(1, 100), Err(ConfigError::Density { value: 100 }));
+    }
+
+    #[test]
+    fn the_first_reveal_opens_an_area_that_crosses_chunk_edges() {
+        // A click on a chunk corner: the opening itself already reaches into
+        // all four chunks that meet there.
+        let mut world = world(3);
+        world.reveal(0, 0);
+        assert_eq!(world.tile(0, 0), Tile::Open(0));
+        assert!(world.score() > 9);
+        for (row, col) in neighbours(0, 0) {
+            assert!(matches!(world.tile(row, col), Tile::Open(_)));
+        }
+        // Every uncovered number is right, and a blank never borders a
+        // covered cell.
+        let seen: Vec<_> = (-3 * CHUNK..3 * CHUNK)
+            .flat_map(|row| (-3 * CHUNK..3 * CHUNK).map(move |col| (row, col)))
+            .collect();
+        for &(row, col) in &seen {
+            if let Tile::Open(count) = world.tile(row, col) {
+                assert_eq!(world.count(row, col), count);
+                if count == 0 {
+                    assert!(neighbours(row, col)
+                        .all(|(row, col)| matches!(world.tile(row, col), Tile::Open(_))));
+                }
+            }
+        }
+        let opened = seen
+            .iter()
+            .filter(|&&(row, col)| matches!(world.tile(row, col), Tile::Open(_)))
+            .count();
+        assert_eq!(opened as u64, world.score());
+    }
+
+    #[test]
+    fn only_the_chunks_near_the_uncovered_cells_are_drawn() {
+        let mut world = world(3);
+        assert_eq!(world.chunks(), 0);
+        assert_eq!(world.tile(1_000_000, -1_000_000), Tile::Covered);
+        assert_eq!(world.chunks(), 0);
+        world.reveal(0, 0);
+        assert!(world.chunks() < 64, "drew {} chunks", world.chunks());
+    }
+
+    #[test]
+    fn a_mine_ends_the_game_and_freezes_the_score() {
+        let mut world = world(8);
+        world.reveal(0, 0);
+        let score = world.score();
+        let mine = find(&mut world, true);
+        world.reveal(mine.0, mine.1);
+        assert_eq!(world.tile(mine.0, mine.1), Tile::Exploded);
+        assert!(world.is_over());
+        let safe = find(&mut world, false);
+        world.reveal(safe.0, safe.1);
+        world.flag(safe.0, safe.1);
+        assert_eq!(world.tile(safe.0, safe.1), Tile::Covered);
+        assert_eq!(world.score(), score);
+    }
+
+    #[test]
+    fn a_flag_holds_off_reveals_and_chording_needs_the_full_count() {
+        let mut world = world(8);
+        world.reveal(0, 0);
+        let mine = find(&mut world, true);
+        world.flag(mine.0, mine.1);
+        world.reveal(mine.0, mine.1);
+        assert_eq!(world.tile(mine.0, mine.1), Tile::Flagged);
+        assert!(!world.is_over());
+        world.flag(mine.0, mine.1);
+        assert_eq!(world.tile(mine.0, mine.1), Tile::Covered);
+
+        // A number on the far side of the origin, with its mines flagged,
+        // chords open everything else around it.
+        let (row, col) = (-CHUNK * 3..-CHUNK)
+            .flat_map(|row| (0..CHUNK).map(move |col| (row, col)))
+            .find(|&(row, col)| !world.is_mine(row, col) && world.count(row, col) > 0)
+            .expect("a number");
+        world.reveal(row, col);
+        let count = world.count(row, col);
+        let mines: Vec<_> = neighbours(row, col)
+            .filter(|&(row, col)| world.is_mine(row, col))
+            .collect();
+        let (last, rest) = mines.split_last().expect("a number has mines");
+        for &(row, col) in rest {
+            world.flag(row, col);
+        }
+        let before = world.score();
+        world.chord(row, col);
+        assert_eq!(world.score(), before, "one flag short of {count}");
+        world.flag(last.0, last.1);
+        world.chord(row, col);
+        assert!(!world.is_over());
+        assert!(neighbours(row, col).all(|(row, col)| world.tile(row, col) != Tile::Covered));
+    }
+
+    #[test]
+    fn negative_coordinates_land_in_their_own_chunks() {
+        assert_eq!(locate(-1, -1), ((-1, -1), (CHUNK * CHUNK - 1) as usize));
+        assert_eq!(locate(-CHUNK, 0), ((-1, 0), 0));
+        assert_eq!(locate(CHUNK, -CHUNK - 1), ((1, -2), (CHUNK - 1) as usize));
+        let mut world = world(2);
+        world.reveal(-1, -1);
+        assert_eq!(world.tile(-1, -1), Tile::Open(0));
+        assert_eq!(world.tile(0, 0), Tile::Open(world.count(0, 0)));
+    }
+}
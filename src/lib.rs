@@ -1,10 +1,45 @@
-//! Minesweeper: pure rules in [`game`], browser rendering in `app`.
+//! Minesweeper: pure rules in [`game`], played in a browser by `app`, in a
+//! terminal by `terminal` and by bots through the `engine` binary.
 //!
-//! `app` is compiled only for `wasm32`, which lets `cargo test` exercise the
-//! rules on the host toolchain without pulling in a DOM.
+//! The rules and what is built on them:
+//!
+//! - [`config`] reads and checks the board settings; [`geometry`] says which
+//!   cells touch; [`generator`] draws layouts the same way on every platform.
+//! - [`game`] holds the board and the moves, [`history`] undo and redo, and
+//!   [`clock`] the timer.
+//! - [`solver`] proves cells safe or mined from what the player can see,
+//!   [`probability`] weighs the rest, and [`analysis`] measures a laid-out
+//!   board.
+//! - [`replay`] records a game as its seed and moves, [`save`] as a picture
+//!   of the board, and [`share`] packs a board into a short code; [`daily`]
+//!   derives one from the date.
+//! - [`infinite`] is the endless board.
+//! - [`protocol`] is the line protocol the `engine` binary speaks on stdin
+//!   and stdout; [`protocol::Engine::handle`] answers one line.
+//!
+//! `app` is compiled only for `wasm32` and `terminal` only off it, which lets
+//! `cargo test` exercise everything but the browser on the host toolchain
+//! without pulling in a DOM.
 
+pub mod analysis;
+pub mod clock;
 pub mod config;
+pub mod daily;
+mod format;
 pub mod game;
+pub mod generator;
+pub mod geometry;
+pub mod history;
+pub mod infinite;
+pub mod probability;
+pub mod protocol;
+pub mod replay;
+pub mod save;
+pub mod share;
+pub mod solver;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod terminal;
 
 #[cfg(target_arch = "wasm32")]
 pub mod app;
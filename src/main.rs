@@ -1,14 +1,44 @@
 #[cfg(target_arch = "wasm32")]
 fn main() {
     use minesweeper::app::{App, Props};
-    use minesweeper::config::Config;
+    use minesweeper::config::{self, Config};
 
     yew::start_app_with_props::<App>(Props {
         config: Config::from_build_env(),
+        density: config::density_from_build_env(),
     });
 }
 
+/// Plays in the terminal; `--help` lists the flags and keys.
 #[cfg(not(target_arch = "wasm32"))]
-fn main() {
-    println!("minesweeper renders in a browser: run `trunk serve --open`");
+fn main() -> std::process::ExitCode {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::process::ExitCode;
+
+    use minesweeper::terminal::{self, Args};
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match terminal::parse_args(&args) {
+        Ok(Args::Help) => {
+            println!("{}", terminal::USAGE);
+            ExitCode::SUCCESS
+        }
+        Ok(Args::Play { config, seed }) => {
+            // `rand` is a browser-only dependency; std's hasher keys are
+            // random per process, which is all a seed needs.
+            let seed = seed.unwrap_or_else(|| RandomState::new().build_hasher().finish());
+            match terminal::run(config, seed) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(error) => {
+                    eprintln!("minesweeper: {error}");
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Err(error) => {
+            eprintln!("minesweeper: {error}\n\n{}", terminal::USAGE);
+            ExitCode::from(2)
+        }
+    }
 }
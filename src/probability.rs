@@ -0,0 +1,548 @@
+//! Exact mine probabilities for every covered cell.
+//!
+//! Like [`solver`](crate::solver), this takes a [`PlayerBoard`] and so reads
+//! only what the player can see: uncovered numbers, uncovered mines and the
+//! total from `Config::mines`. Flags are ignored.
+//!
+//! Covered cells touching a number (the frontier) are split into independent
+//! components, each enumerated by backtracking into a table of "how many
+//! layouts place `k` mines here". The components are then convolved together
+//! and every frontier total `m` is weighted by the number of ways to put the
+//! remaining mines in the interior, `C(interior, mines - m)`. Interior cells
+//! are interchangeable, so they share one probability.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::game::{PlayerBoard, PlayerView};
+
+/// Branches the enumeration may take, across every component, before it gives
+/// up. Play keeps the frontier to a few tangled boxes, but cells uncovered far
+/// apart can knot one component that would take minutes to count; past this
+/// the answer is `None`, within a tenth of a second in release.
+pub const MOST_STEPS: usize = 1 << 20;
+
+/// Per-cell mine probability; `None` for cells that are already uncovered,
+/// and for gaps in the mask.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Probabilities {
+    width: usize,
+    cells: Vec<Option<f64>>,
+    interior: Option<f64>,
+}
+
+impl Probabilities {
+    pub fn get(&self, row: usize, col: usize) -> Option<f64> {
+        self.cells[row * self.width + col]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Option<f64>]> {
+        self.cells.chunks(self.width)
+    }
+
+    /// The shared probability of every covered cell no number touches, or
+    /// `None` if there are no such cells.
+    pub fn interior(&self) -> Option<f64> {
+        self.interior
+    }
+}
+
+/// "Exactly `mines` of these frontier cells are mines", from one number.
+struct Constraint {
+    cells: Vec<usize>,
+    mines: usize,
+}
+
+/// Layout counts for one component, indexed by how many mines it holds.
+struct Tally {
+    /// Cells touching exactly the same numbers are interchangeable, so they
+    /// are enumerated together as one box holding 0..=len mines.
+    boxes: Vec<Vec<usize>>,
+    layouts: Vec<f64>,
+    /// `per_box[b][k]`: mines in box `b`, summed over layouts with `k` mines.
+    per_box: Vec<Vec<f64>>,
+}
+
+/// Probabilities for the board as the player sees it, or `None` if no mine
+/// layout is consistent with it (which a board built by the rules never is).
+/// Also `None` when the config stacks mines: every layout here holds at most
+/// one per cell, and when counting the layouts would take more than
+/// [`MOST_STEPS`], so a call never stalls the page.
+pub fn mine_probabilities(board: &PlayerBoard) -> Option<Probabilities> {
+    let config = board.config();
+    if config.mines_per_cell > 1 {
+        return None;
+    }
+    let positions: Vec<(usize, usize)> = (0..config.rows())
+        .flat_map(|row| (0..config.width).map(move |col| (row, col)))
+        .collect();
+    let index = |(row, col): (usize, usize)| row * config.width + col;
+    let covered: Vec<bool> = positions
+        .iter()
+        .map(|&(row, col)| board.view(row, col).is_covered())
+        .collect();
+    let exposed = positions
+        .iter()
+        .filter(|&&(row, col)| matches!(board.view(row, col), PlayerView::Mine(_)))
+        .count();
+    let mines = config.mines.checked_sub(exposed)?;
+
+    let mut constraints = Vec::new();
+    for &(row, col) in &positions {
+        let PlayerView::Number(count) = board.view(row, col) else {
+            continue;
+        };
+        let mut cells = Vec::new();
+        let mut seen = 0;
+        for neighbor in board.neighbors(row, col) {
+            match board.view(neighbor.0, neighbor.1) {
+                PlayerView::Mine(_) => seen += 1,
+                other if other.is_covered() => cells.push(index(neighbor)),
+                _ => {}
+            }
+        }
+        let needed = (count as usize).checked_sub(seen)?;
+        if needed > cells.len() {
+            return None;
+        }
+        if !cells.is_empty() {
+            constraints.push(Constraint {
+                cells,
+                mines: needed,
+            });
+        }
+    }
+
+    let mut touching: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+    for (id, constraint) in constraints.iter().enumerate() {
+        for &cell in &constraint.cells {
+            touching[cell].push(id);
+        }
+    }
+    let interior: Vec<usize> = (0..positions.len())
+        .filter(|&cell| covered[cell] && touching[cell].is_empty())
+        .collect();
+
+    let mut budget = MOST_STEPS;
+    let tallies: Vec<Tally> = components(&touching, &constraints)
+        .into_iter()
+        .map(|cells| enumerate(&cells, &touching, &constraints, mines, &mut budget))
+        .collect::<Option<_>>()?;
+    if tallies
+        .iter()
+        .any(|tally| tally.layouts.iter().all(|&n| n == 0.0))
+    {
+        return None;
+    }
+
+    let weights = interior_weights(interior.len(), mines);
+    let all = tallies
+        .iter()
+        .fold(vec![1.0], |acc, tally| convolve(&acc, &tally.layouts));
+    let total: f64 = weighted(&all, &weights, |_| 1.0);
+    if total == 0.0 {
+        return None;
+    }
+
+    let mut cells = vec![None; positions.len()];
+    for (id, tally) in tallies.iter().enumerate() {
+        let rest = tallies
+            .iter()
+            .enumerate()
+            .filter(|&(other, _)| other != id)
+            .fold(vec![1.0], |acc, (_, other)| convolve(&acc, &other.layouts));
+        for (cells_in_box, per_box) in tally.boxes.iter().zip(&tally.per_box) {
+            let joint = convolve(per_box, &rest);
+            let expected = weighted(&joint, &weights, |_| 1.0) / total;
+            for &cell in cells_in_box {
+                cells[cell] = Some(expected / cells_in_box.len() as f64);
+            }
+        }
+    }
+    let interior_probability = match interior.len() {
+        0 => None,
+        size => {
+            let expected = weighted(&all, &weights, |m| (mines - m) as f64 / size as f64);
+            Some(expected / total)
+        }
+    };
+    for &cell in &interior {
+        cells[cell] = interior_probability;
+    }
+    Some(Probabilities {
+        width: config.width,
+        cells,
+        interior: interior_probability,
+    })
+}
+
+/// Groups frontier cells that share a constraint, transitively. Breadth-first
+/// order keeps neighbouring cells adjacent in the enumeration, which lets the
+/// backtracking prune early.
+fn components(touching: &[Vec<usize>], constraints: &[Constraint]) -> Vec<Vec<usize>> {
+    let mut visited = vec![false; touching.len()];
+    let mut components = Vec::new();
+    for start in 0..touching.len() {
+        if visited[start] || touching[start].is_empty() {
+            continue;
+        }
+        visited[start] = true;
+        let mut queue = VecDeque::from([start]);
+        let mut component = Vec::new();
+        while let Some(cell) = queue.pop_front() {
+            component.push(cell);
+            for &id in &touching[cell] {
+                for &next in &constraints[id].cells {
+                    if !visited[next] {
+                        visited[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+/// Counts every assignment of mines to `cells` that satisfies all of their
+/// constraints, by how many mines it uses, or `None` once the search has
+/// taken every step left in `budget`.
+fn enumerate(
+    cells: &[usize],
+    touching: &[Vec<usize>],
+    constraints: &[Constraint],
+    limit: usize,
+    budget: &mut usize,
+) -> Option<Tally> {
+    let mut boxes: Vec<Vec<usize>> = Vec::new();
+    let mut box_of: BTreeMap<&[usize], usize> = BTreeMap::new();
+    for &cell in cells {
+        let id = *box_of.entry(&touching[cell]).or_insert_with(|| {
+            boxes.push(Vec::new());
+            boxes.len() - 1
+        });
+        boxes[id].push(cell);
+    }
+    let mut search = Search {
+        touching: boxes.iter().map(|cells| &touching[cells[0]][..]).collect(),
+        sizes: boxes.iter().map(Vec::len).collect(),
+        constraints,
+        placed: vec![0; constraints.len()],
+        open: constraints.iter().map(|c| c.cells.len()).collect(),
+        chosen: vec![0; boxes.len()],
+        limit,
+        layouts: vec![0.0; cells.len() + 1],
+        per_box: vec![vec![0.0; cells.len() + 1]; boxes.len()],
+        budget: *budget,
+    };
+    search.run(0, 0, 1.0);
+    if search.budget == 0 {
+        return None;
+    }
+    *budget = search.budget;
+    Some(Tally {
+        boxes,
+        layouts: search.layouts,
+        per_box: search.per_box,
+    })
+}
+
+struct Search<'a> {
+    /// The constraints each box sits under.
+    touching: Vec<&'a [usize]>,
+    sizes: Vec<usize>,
+    constraints: &'a [Constraint],
+    /// Mines assigned so far under each constraint.
+    placed: Vec<usize>,
+    /// Cells under each constraint whose box is not yet assigned.
+    open: Vec<usize>,
+    /// Mines assigned to each box on the current branch.
+    chosen: Vec<usize>,
+    limit: usize,
+    layouts: Vec<f64>,
+    per_box: Vec<Vec<f64>>,
+    /// Steps left; the search stops where it is once they run out.
+    budget: usize,
+}
+
+impl Search<'_> {
+    /// `weight` is the number of ways to realise the box counts chosen so far.
+    fn run(&mut self, slot: usize, mines: usize, weight: f64) {
+        if self.budget == 0 {
+            return;
+        }
+        self.budget -= 1;
+        if slot == self.sizes.len() {
+            self.layouts[mines] += weight;
+            for (per_box, &chosen) in self.per_box.iter_mut().zip(&self.chosen) {
+                per_box[mines] += weight * chosen as f64;
+            }
+            return;
+        }
+        let size = self.sizes[slot];
+        for count in 0..=size.min(self.limit - mines) {
+            if self.assign(slot, count) {
+                self.chosen[slot] = count;
+                self.run(slot + 1, mines + count, weight * choose(size, count));
+            }
+            self.unassign(slot, count);
+        }
+        self.chosen[slot] = 0;
+    }
+
+    /// Puts `count` mines in box `slot` and reports whether every constraint
+    /// on it can still be met. Always paired with `unassign`, feasible or not.
+    fn assign(&mut self, slot: usize, count: usize) -> bool {
+        let mut feasible = true;
+        for &id in self.touching[slot] {
+            self.open[id] -= self.sizes[slot];
+            self.placed[id] += count;
+            let needed = self.constraints[id].mines;
+            feasible &= self.placed[id] <= needed && self.placed[id] + self.open[id] >= needed;
+        }
+        feasible
+    }
+
+    fn unassign(&mut self, slot: usize, count: usize) {
+        for &id in self.touching[slot] {
+            self.open[id] += self.sizes[slot];
+            self.placed[id] -= count;
+        }
+    }
+}
+
+fn choose(n: usize, k: usize) -> f64 {
+    (1..=k).fold(1.0, |acc, i| acc * (n - k + i) as f64 / i as f64)
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        if x == 0.0 {
+            continue;
+        }
+        for (j, &y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+/// `C(interior, mines - m)` for every frontier total `m`, scaled by a common
+/// factor so expert-sized binomials stay inside `f64`.
+fn interior_weights(interior: usize, mines: usize) -> Vec<f64> {
+    let ln_choose = |r: usize| -> f64 {
+        (1..=r)
+            .map(|i| ((interior - r + i) as f64 / i as f64).ln())
+            .sum()
+    };
+    let logs: Vec<Option<f64>> = (0..=mines)
+        .map(|m| {
+            let r = mines - m;
+            (r <= interior).then(|| ln_choose(r))
+        })
+        .collect();
+    let peak = logs.iter().flatten().copied().fold(f64::MIN, f64::max);
+    logs.into_iter()
+        .map(|log| log.map_or(0.0, |log| (log - peak).exp()))
+        .collect()
+}
+
+/// `sum over m of layouts[m] * weights[m] * scale(m)`.
+fn weighted(layouts: &[f64], weights: &[f64], scale: impl Fn(usize) -> f64) -> f64 {
+    layouts
+        .iter()
+        .zip(weights)
+        .enumerate()
+        .map(|(m, (&count, &weight))| count * weight * scale(m))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::game::fixtures::layout;
+    use crate::game::{Board, Cell};
+    use crate::generator::Generator;
+    use crate::solver::deduce;
+
+    /// Tries every placement of the remaining mines over the covered cells and
+    /// keeps those that reproduce every visible number.
+    fn brute_force(board: &Board) -> Vec<Option<f64>> {
+        let config = board.config();
+        let positions: Vec<(usize, usize)> = (0..config.rows())
+            .flat_map(|row| (0..config.width).map(move |col| (row, col)))
+            .collect();
+        let covered: Vec<(usize, usize)> = positions
+            .iter()
+            .copied()
+            .filter(|&(r, c)| !board.cell(r, c).uncovered)
+            .collect();
+        let exposed = |p: (usize, usize)| {
+            let cell = board.cell(p.0, p.1);
+            cell.uncovered && cell.content.is_mine()
+        };
+        let hidden = config.mines - positions.iter().filter(|&&p| exposed(p)).count();
+        let mut hits = vec![0u64; covered.len()];
+        let mut layouts = 0u64;
+        for mask in 0u32..1 << covered.len() {
+            if mask.count_ones() as usize != hidden {
+                continue;
+            }
+            let mined = |p: (usize, usize)| {
+                covered
+                    .iter()
+                    .position(|&q| q == p)
+                    .is_some_and(|i| mask & (1 << i) != 0)
+            };
+            let consistent = positions.iter().all(|&(r, c)| {
+                let cell = board.cell(r, c);
+                match (cell.uncovered, cell.content) {
+                    (true, Cell::Adjacent(n)) => {
+                        let around = board.neighbors(r, c).filter(|&p| mined(p) || exposed(p));
+                        around.count() == n as usize
+                    }
+                    _ => true,
+                }
+            });
+            if consistent {
+                layouts += 1;
+                for (i, hit) in hits.iter_mut().enumerate() {
+                    *hit += u64::from(mask & (1 << i) != 0);
+                }
+            }
+        }
+        positions
+            .iter()
+            .map(|p| {
+                let i = covered.iter().position(|q| q == p)?;
+                Some(hits[i] as f64 / layouts as f64)
+            })
+            .collect()
+    }
+
+    fn assert_matches_brute_force(board: &Board) {
+        let exact = mine_probabilities(&board.player_board()).expect("consistent board");
+        let expected = brute_force(board);
+        let actual: Vec<Option<f64>> = exact.rows().flatten().copied().collect();
+        for (i, (a, e)) in actual.iter().zip(&expected).enumerate() {
+            match (a, e) {
+                (Some(a), Some(e)) => assert!((a - e).abs() < 1e-9, "cell {i}: {a} vs {e}"),
+                _ => assert_eq!(a, e, "cell {i}"),
+            }
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_on_a_single_frontier() {
+        assert_matches_brute_force(&layout(&["*.*.", "oooo", "oooo"]));
+    }
+
+    #[test]
+    fn matches_brute_force_with_interior_cells_in_play() {
+        assert_matches_brute_force(&layout(&["*...*", ".....", "oo.*.", "ooo.."]));
+    }
+
+    #[test]
+    fn matches_brute_force_across_two_independent_components() {
+        assert_matches_brute_force(&layout(&["*.oo.*", "ooooo.", "......", "*..*.."]));
+    }
+
+    #[test]
+    fn matches_brute_force_when_a_number_touches_an_exposed_mine() {
+        let board = layout(&["xo..", "oo*.", "...."]);
+        assert_matches_brute_force(&board);
+        let exact = mine_probabilities(&board.player_board()).expect("consistent board");
+        assert_eq!(
+            exact.get(0, 0),
+            None,
+            "the exposed mine is no longer covered"
+        );
+    }
+
+    #[test]
+    fn an_untouched_board_is_uniform() {
+        let config = Config::new(5, 4, 6).expect("valid config");
+        let probabilities =
+            mine_probabilities(&Board::new(config).player_board()).expect("consistent");
+        assert_eq!(probabilities.interior(), Some(6.0 / 20.0));
+        assert!(probabilities
+            .rows()
+            .flatten()
+            .all(|&p| p == Some(6.0 / 20.0)));
+    }
+
+    #[test]
+    fn deduced_cells_come_out_as_certainties() {
+        let board = layout(&["o*..", "oo..", "oo.."]);
+        let probabilities = mine_probabilities(&board.player_board()).expect("consistent");
+        let known = deduce(&board.player_board());
+        for &(r, c) in &known.safe {
+            assert_eq!(probabilities.get(r, c), Some(0.0));
+        }
+        for &(r, c) in &known.mines {
+            assert_eq!(probabilities.get(r, c), Some(1.0));
+        }
+    }
+
+    /// An expert board opened at its centre, then `scattered` more safe cells
+    /// uncovered one by one wherever the draw puts them, as a player clicking
+    /// all over the board would.
+    fn expert_mid_game(seed: u64, scattered: usize) -> Board {
+        let config = Config::new(30, 16, 99).expect("valid config");
+        let mut board = Board::new(config.clone());
+        let mut rng = Generator::new(seed);
+        board.reveal(8, 15, &mut rng);
+        let width = config.width;
+        let covered: Vec<usize> = config
+            .positions()
+            .filter(|&(r, c)| !board.cell(r, c).uncovered && !board.cell(r, c).content.is_mine())
+            .map(|(r, c)| r * width + c)
+            .collect();
+        let picked = rng.choose(&covered, scattered);
+        let rows: Vec<String> = (0..config.height)
+            .map(|r| {
+                (0..width)
+                    .map(|c| match board.cell(r, c) {
+                        cell if cell.content.is_mine() => '*',
+                        cell if cell.uncovered || picked.contains(&(r * width + c)) => 'o',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        layout(&rows.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn a_mid_game_expert_frontier_is_counted_exactly() {
+        for seed in 0..3 {
+            let board = expert_mid_game(seed, 40);
+            let probabilities = mine_probabilities(&board.player_board()).expect("countable");
+            let expected: f64 = probabilities.rows().flatten().flatten().sum();
+            assert!((expected - 99.0).abs() < 1e-6, "seed {seed}: {expected}");
+        }
+    }
+
+    #[test]
+    fn a_frontier_too_tangled_to_count_gives_up_instead_of_stalling() {
+        // Minutes of enumeration without the step budget.
+        assert_eq!(
+            mine_probabilities(&expert_mid_game(1, 120).player_board()),
+            None
+        );
+    }
+
+    #[test]
+    fn probabilities_on_an_expert_board_sum_to_the_mines_still_hidden() {
+        let config = Config::new(30, 16, 99).expect("valid config");
+        for seed in 0..5 {
+            let mut board = Board::new(config.clone());
+            board.reveal(8, 15, &mut Generator::new(seed));
+            let probabilities = mine_probabilities(&board.player_board()).expect("consistent");
+            let expected: f64 = probabilities.rows().flatten().flatten().sum();
+            assert!((expected - 99.0).abs() < 1e-6, "seed {seed}: {expected}");
+        }
+    }
+}
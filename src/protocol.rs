@@ -0,0 +1,265 @@
+//! The engine protocol: a game played over text lines, for bots in any
+//! language.
+//!
+//! The `engine` binary reads one command per line on stdin and answers each
+//! on stdout; [`Engine::handle`] is the whole of it, so it is tested here
+//! without a process. Every answer tells the bot only what a player could
+//! see: covered cells are covered, whatever is under them.
+//!
+//! ```text
+//! new <seed> [<setting>=<value>]...   start a game   -> ok <rows> <cols> <mines>
+//! reveal <row> <col>                  uncover a cell -> ok playing|won|lost
+//! flag <row> <col>                    cycle a mark   -> ok playing|won|lost
+//! chord <row> <col>                   open around it -> ok playing|won|lost
+//! board                               the position   -> board <rows> <cols> <mines left>
+//!                                                       then one line per row
+//! result                              the outcome    -> result playing|won|lost
+//! quit                                end the session, no answer
+//! ```
+//!
+//! Settings are the board's build variables, every one in
+//! [`VARS`](crate::config::VARS), named in lower case with dashes, as the
+//! terminal flags are (`width=30 height=16 mines=99 no-guess=true`), and are
+//! checked the same way, the cap on board size included; unset ones take the
+//! build defaults. The endless board's `density` is not a setting. `<rows>` counts every layer of a cube, and coordinates are
+//! zero-based. A board row is one space-separated token per cell: `-`
+//! covered, `F` flagged (`F2` for two mines on a stacked board), `?`
+//! question-marked, a number for an uncovered cell, `*` for an uncovered mine
+//! and `x` for a gap in a masked board.
+//!
+//! Anything that cannot be done answers `error <reason>` and leaves the game
+//! as it was, so one bad line never ends a session; that includes a first
+//! reveal a no-guess board finds no layout for.
+
+use std::collections::HashMap;
+
+use crate::config::{self, Config};
+use crate::game::{Action, GameResult, GameState, PlayerView};
+use crate::generator::Generator;
+
+/// One session's state: the game in progress, if one was started.
+#[derive(Clone, Debug, Default)]
+pub struct Engine {
+    game: Option<(GameState, Generator)>,
+}
+
+impl Engine {
+    /// The answer to one command line, or `None` once the bot says `quit`.
+    /// A multi-line answer is joined with `\n` and has no trailing newline.
+    pub fn handle(&mut self, line: &str) -> Option<String> {
+        let mut words = line.split_whitespace();
+        let answer = match (words.next(), self.game.as_mut()) {
+            (Some("quit"), _) => return None,
+            (Some("new"), _) => self.start(words),
+            (None, _) => Err("empty command".to_owned()),
+            (Some("reveal" | "flag" | "chord" | "board" | "result"), None) => {
+                Err("no game; start one with new".to_owned())
+            }
+            (Some(command @ ("reveal" | "flag" | "chord")), Some((state, rng))) => {
+                cell(state, words).and_then(|(row, col)| {
+                    let action = match command {
+                        "reveal" => Action::Reveal(row, col),
+                        "flag" => Action::Flag(row, col),
+                        _ => Action::Chord(row, col),
+                    };
+                    let view = state.board.view(row, col);
+                    let covered = matches!(view, PlayerView::Covered | PlayerView::Questioned);
+                    // Tried on copies, so a no-guess board that finds no
+                    // layout answers an error and changes nothing.
+                    if command == "reveal" && covered && !state.is_over() {
+                        let mut board = state.board.clone();
+                        board
+                            .lay_mines(row, col, &mut rng.clone())
+                            .map_err(|error| error.to_string())?;
+                    }
+                    *state = state.apply(action, rng);
+                    Ok(format!("ok {}", outcome(state)))
+                })
+            }
+            (Some("board"), Some((state, _))) => Ok(board(state)),
+            (Some("result"), Some((state, _))) => Ok(format!("result {}", outcome(state))),
+            (Some(command), _) => Err(format!("unknown command {command:?}")),
+        };
+        Some(answer.unwrap_or_else(|reason| format!("error {reason}")))
+    }
+
+    fn start<'a>(&mut self, mut words: impl Iterator<Item = &'a str>) -> Result<String, String> {
+        let seed = words
+            .next()
+            .ok_or("new needs a seed")?
+            .parse()
+            .map_err(|_| "the seed must be a whole number")?;
+        let mut values = HashMap::new();
+        for word in words {
+            let (name, value) = word
+                .split_once('=')
+                .ok_or_else(|| format!("expected <setting>=<value>, got {word:?}"))?;
+            let var = config::var_named(name).ok_or_else(|| format!("unknown setting {name:?}"))?;
+            values.insert(var, value);
+        }
+        let config = Config::from_vars(|var| values.get(var).copied())
+            .map_err(|error| format!("configuration error: {error}"))?;
+        let answer = format!(
+            "ok {} {} {}",
+            config.height * config.depth,
+            config.width,
+            config.mines
+        );
+        self.game = Some((GameState::new(config), Generator::new(seed)));
+        Ok(answer)
+    }
+}
+
+/// The `<row> <col>` of a move, on the board.
+fn cell<'a>(
+    state: &GameState,
+    mut words: impl Iterator<Item = &'a str>,
+) -> Result<(usize, usize), String> {
+    let mut coordinate = || -> Result<usize, String> {
+        let word = words.next().ok_or("expected <row> <col>")?;
+        word.parse()
+            .map_err(|_| format!("{word:?} is not a coordinate"))
+    };
+    let (row, col) = (coordinate()?, coordinate()?);
+    let config = state.board.config();
+    if row >= config.height * config.depth || col >= config.width {
+        return Err(format!("{row} {col} is off the board"));
+    }
+    Ok((row, col))
+}
+
+fn outcome(state: &GameState) -> &'static str {
+    match state.result {
+        None => "playing",
+        Some(GameResult::Won) => "won",
+        Some(GameResult::Lost) => "lost",
+    }
+}
+
+fn board(state: &GameState) -> String {
+    let board = &state.board;
+    let config = board.config();
+    let mut lines = vec![format!(
+        "board {} {} {}",
+        config.height * config.depth,
+        config.width,
+        board.mines_remaining()
+    )];
+    lines.extend(
+        board
+            .view_rows()
+            .map(|views| views.into_iter().map(token).collect::<Vec<_>>().join(" ")),
+    );
+    lines.join("\n")
+}
+
+fn token(view: PlayerView) -> String {
+    match view {
+        PlayerView::Gap => "x".to_owned(),
+        PlayerView::Covered => "-".to_owned(),
+        PlayerView::Questioned => "?".to_owned(),
+        PlayerView::Flagged(1) => "F".to_owned(),
+        PlayerView::Flagged(count) => format!("F{count}"),
+        PlayerView::Number(count) => count.to_string(),
+        PlayerView::Mine(_) => "*".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigError;
+
+    fn answer(engine: &mut Engine, line: &str) -> String {
+        engine.handle(line).expect("not a quit")
+    }
+
+    #[test]
+    fn a_session_starts_a_game_and_plays_it() {
+        let mut engine = Engine::default();
+        assert_eq!(
+            answer(&mut engine, "reveal 0 0"),
+            "error no game; start one with new"
+        );
+        assert_eq!(
+            answer(&mut engine, "new 3 width=5 height=4 mines=2"),
+            "ok 4 5 2"
+        );
+        assert_eq!(answer(&mut engine, "result"), "result playing");
+        assert_eq!(answer(&mut engine, "flag 3 4"), "ok playing");
+        let board = answer(&mut engine, "board");
+        let mut lines = board.lines();
+        assert_eq!(lines.next(), Some("board 4 5 1"));
+        assert_eq!(lines.clone().count(), 4);
+        assert!(lines.all(|line| line.split(' ').count() == 5));
+        assert_eq!(engine.handle("quit"), None);
+    }
+
+    #[test]
+    fn the_board_never_shows_a_covered_mine() {
+        let mut engine = Engine::default();
+        answer(&mut engine, "new 1 width=9 height=9 mines=10");
+        assert_eq!(answer(&mut engine, "reveal 4 4"), "ok playing");
+        let board = answer(&mut engine, "board");
+        assert!(!board.contains('*'));
+        let (state, _) = engine.game.as_ref().expect("a game");
+        for (row, line) in board.lines().skip(1).enumerate() {
+            for (col, token) in line.split(' ').enumerate() {
+                match state.board.view(row, col) {
+                    PlayerView::Number(count) => assert_eq!(token, count.to_string()),
+                    view => assert_eq!((view, token), (PlayerView::Covered, "-")),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_bad_line_is_answered_with_an_error_and_changes_nothing() {
+        let mut engine = Engine::default();
+        assert_eq!(answer(&mut engine, "new"), "error new needs a seed");
+        assert_eq!(
+            answer(&mut engine, "new 1 width=0"),
+            format!(
+                "error configuration error: {}",
+                Config::new(0, 9, 1).expect_err("zero width")
+            )
+        );
+        let oversized = answer(&mut engine, "new 1 width=100000 height=100000 mines=10");
+        assert_eq!(
+            oversized,
+            format!(
+                "error configuration error: {}",
+                ConfigError::TooManyCells {
+                    width: 100_000,
+                    height: 100_000,
+                    depth: 1,
+                }
+            )
+        );
+        assert_eq!(
+            answer(&mut engine, "new 1 colour=red"),
+            "error unknown setting \"colour\""
+        );
+        assert_eq!(
+            answer(&mut engine, "new 1 width"),
+            "error expected <setting>=<value>, got \"width\""
+        );
+        answer(&mut engine, "new 1 width=4 height=4 mines=1");
+        let before = answer(&mut engine, "board");
+        assert_eq!(
+            answer(&mut engine, "reveal 4 0"),
+            "error 4 0 is off the board"
+        );
+        assert_eq!(
+            answer(&mut engine, "reveal a 0"),
+            "error \"a\" is not a coordinate"
+        );
+        assert_eq!(answer(&mut engine, "flag 1"), "error expected <row> <col>");
+        assert_eq!(
+            answer(&mut engine, "dig 1 1"),
+            "error unknown command \"dig\""
+        );
+        assert_eq!(answer(&mut engine, "   "), "error empty command");
+        assert_eq!(answer(&mut engine, "board"), before);
+    }
+}
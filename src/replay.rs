@@ -0,0 +1,576 @@
+//! Recorded games.
+//!
+//! A [`Session`] is a game that writes down every action it is given, with the
+//! milliseconds elapsed since the game began. All randomness comes from one
+//! [`Generator`] seeded at the start, so the seed, the generator version, the
+//! config and the action stream are enough for [`Replay::states`] to rebuild
+//! every intermediate state exactly, undo and redo included, with any build
+//! of the game that knows that version. A restart is never a step: it begins
+//! a new recording with a new seed, so a replay that claims one is refused.
+//!
+//! The text format is line-oriented and meant to survive a copy and paste:
+//!
+//! ```text
+//! minesweeper-replay 2
+//! width 10
+//! height 10
+//! depth 1
+//! mines 15
+//! mines-per-cell 1
+//! lives 1
+//! question-marks false
+//! generation uniform
+//! first-click opening
+//! undo loss-is-final
+//! topology flat
+//! grid square
+//! kernel moore
+//! seed 4242
+//! generator 1
+//! 0 reveal 4 4
+//! 1532 flag 3 3
+//! 2210 chord 4 4
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::clock::{Clock, Stopwatch};
+use crate::config::{Config, ConfigError};
+use crate::format::{self, HeaderError, Settings, SettingsError};
+use crate::game::{Action, GameState, NoGuessError, PlayerView};
+use crate::generator::{Generator, Version};
+use crate::history::History;
+use crate::share::ShareCode;
+
+const HEADER: &str = "minesweeper-replay";
+/// Version 1 drew its boards with `rand`'s `StdRng` and cannot be replayed.
+const VERSION: u32 = 2;
+
+/// One recorded action and when it happened, in milliseconds since the start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub at: u64,
+    pub action: Action,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub config: Config,
+    pub seed: u64,
+    pub generator: Version,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    MissingHeader,
+    UnsupportedVersion(String),
+    /// A required `key value` line is absent.
+    MissingField(&'static str),
+    /// 1-based line number and the offending text.
+    BadLine {
+        line: usize,
+        text: String,
+    },
+    Config(ConfigError),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "not a replay: expected {HEADER:?} first"),
+            Self::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "replay version {version} is not supported (expected {VERSION})"
+                )
+            }
+            Self::MissingField(key) => write!(f, "replay has no {key:?} line"),
+            Self::BadLine { line, text } => write!(f, "line {line} is not understood: {text:?}"),
+            Self::Config(error) => write!(f, "replay describes an invalid board: {error}"),
+        }
+    }
+}
+
+impl Replay {
+    pub fn new(config: Config, seed: u64, generator: Version) -> Self {
+        Self {
+            config,
+            seed,
+            generator,
+            steps: Vec::new(),
+        }
+    }
+
+    /// The code that opens this game's board for someone else.
+    pub fn code(&self) -> ShareCode {
+        ShareCode {
+            config: self.config.clone(),
+            seed: self.seed,
+            generator: self.generator,
+        }
+    }
+
+    /// Every state the game passed through, starting with the untouched board
+    /// and followed by one state per step.
+    pub fn states(&self) -> Vec<GameState> {
+        let mut rng = Generator::with_version(self.generator, self.seed);
+        let mut history = History::new(self.config.clone());
+        let mut states = vec![history.present().clone()];
+        for step in &self.steps {
+            history = history.apply(step.action, &mut rng);
+            states.push(history.present().clone());
+        }
+        states
+    }
+
+    pub fn final_state(&self) -> GameState {
+        self.states()
+            .pop()
+            .expect("states always holds the opening board")
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER} {VERSION}")?;
+        format::write_settings(f, &self.config)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "generator {}", self.generator.number())?;
+        for step in &self.steps {
+            writeln!(f, "{} {}", step.at, action_text(step.action))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = ReplayError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = format::numbered_lines(text);
+        format::expect_header(&mut lines, HEADER, VERSION..=VERSION).map_err(
+            |error| match error {
+                HeaderError::Missing => ReplayError::MissingHeader,
+                HeaderError::Unsupported(version) => ReplayError::UnsupportedVersion(version),
+            },
+        )?;
+        let mut settings = Settings::default();
+        let mut seed = None;
+        let mut generator = None;
+        let mut steps = Vec::new();
+        for (line, text) in lines {
+            let bad = || ReplayError::BadLine {
+                line,
+                text: text.to_owned(),
+            };
+            match text.split_once(' ') {
+                Some((at, action)) if at.bytes().all(|b| b.is_ascii_digit()) => steps.push(Step {
+                    at: at.parse().map_err(|_| bad())?,
+                    action: parse_action(action).ok_or_else(bad)?,
+                }),
+                Some(("seed", value)) => seed = Some(value.trim().parse().map_err(|_| bad())?),
+                Some(("generator", value)) => {
+                    let number = value.trim().parse().map_err(|_| bad())?;
+                    generator = Some(Version::from_number(number).ok_or_else(bad)?);
+                }
+                Some((key, value)) => settings.set(key, value.trim()).ok_or_else(bad)?,
+                None => return Err(bad()),
+            }
+        }
+        let config = settings.config().map_err(|error| match error {
+            SettingsError::Missing(key) => ReplayError::MissingField(key),
+            SettingsError::Invalid(error) => ReplayError::Config(error),
+        })?;
+        Ok(Self {
+            config,
+            seed: seed.ok_or(ReplayError::MissingField("seed"))?,
+            generator: generator.ok_or(ReplayError::MissingField("generator"))?,
+            steps,
+        })
+    }
+}
+
+/// A game in progress that records itself into a [`Replay`].
+#[derive(Clone, Debug)]
+pub struct Session {
+    history: History,
+    rng: Generator,
+    /// `None` for a game resumed from a save: its opening moves were played
+    /// elsewhere, so no recording of it could replay.
+    replay: Option<Replay>,
+    started: u64,
+    stopwatch: Stopwatch,
+    failure: Option<NoGuessError>,
+}
+
+impl Session {
+    /// Step times are measured on `clock`, which every later call must share.
+    pub fn new(config: Config, seed: u64, clock: &impl Clock) -> Self {
+        Self::from_code(
+            ShareCode {
+                config,
+                seed,
+                generator: Version::LATEST,
+            },
+            clock,
+        )
+    }
+
+    /// Starts the board a share code names, drawn by the generator version
+    /// it was made with.
+    pub fn from_code(code: ShareCode, clock: &impl Clock) -> Self {
+        let ShareCode {
+            config,
+            seed,
+            generator,
+        } = code;
+        Self {
+            history: History::new(config.clone()),
+            rng: Generator::with_version(generator, seed),
+            replay: Some(Replay::new(config, seed, generator)),
+            started: clock.now(),
+            stopwatch: Stopwatch::default(),
+            failure: None,
+        }
+    }
+
+    /// Carries on from a saved position. Recording starts again at the next
+    /// restart; the stopwatch starts now if the saved board was already open.
+    pub fn resume(state: GameState, seed: u64, clock: &impl Clock) -> Self {
+        let now = clock.now();
+        Self {
+            stopwatch: Stopwatch::default().observe(&state, now),
+            history: state.into(),
+            rng: Generator::new(seed),
+            replay: None,
+            started: now,
+            failure: None,
+        }
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+
+    /// Why the last action did nothing, if it was a first reveal on a
+    /// no-guess board that found no layout to clear from there.
+    pub fn failure(&self) -> Option<NoGuessError> {
+        self.failure
+    }
+
+    /// Milliseconds the current game has been running, by `clock`.
+    pub fn elapsed(&self, clock: &impl Clock) -> u64 {
+        self.stopwatch.elapsed(clock.now())
+    }
+
+    pub fn stopwatch(&self) -> Stopwatch {
+        self.stopwatch
+    }
+
+    /// Records and applies `action`. A restart begins a fresh recording whose
+    /// seed is drawn from this one's generator, so a series of games is still
+    /// determined by the first seed. A first reveal that a no-guess board
+    /// finds no layout for changes and records nothing, and
+    /// [`Session::failure`] says why.
+    pub fn apply(&self, action: Action, clock: &impl Clock) -> Self {
+        let mut next = self.clone();
+        if action == Action::Restart {
+            let config = self.history.present().board.config();
+            return Self::new(config.clone(), next.rng.next_u64(), clock);
+        }
+        next.failure = None;
+        let present = self.history.present();
+        if let Action::Reveal(row, col) = action {
+            let view = present.board.view(row, col);
+            if !present.is_over() && matches!(view, PlayerView::Covered | PlayerView::Questioned) {
+                // Tried on copies first, so a failed search records nothing
+                // and the draws the real reveal makes are the same ones.
+                let mut board = present.board.clone();
+                if let Err(error) = board.lay_mines(row, col, &mut self.rng.clone()) {
+                    next.failure = Some(error);
+                    return next;
+                }
+            }
+        }
+        let now = clock.now();
+        next.history = next.history.apply(action, &mut next.rng);
+        next.stopwatch = next.stopwatch.observe(next.history.present(), now);
+        if let Some(replay) = next.replay.as_mut() {
+            replay.steps.push(Step {
+                at: now.saturating_sub(self.started),
+                action,
+            });
+        }
+        next
+    }
+}
+
+fn action_text(action: Action) -> String {
+    match action {
+        Action::Reveal(row, col) => format!("reveal {row} {col}"),
+        Action::Flag(row, col) => format!("flag {row} {col}"),
+        Action::Chord(row, col) => format!("chord {row} {col}"),
+        Action::Undo => "undo".to_owned(),
+        Action::Redo => "redo".to_owned(),
+        Action::Restart => "restart".to_owned(),
+    }
+}
+
+fn parse_action(text: &str) -> Option<Action> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let at = |word: &str| word.parse::<usize>().ok();
+    match words[..] {
+        ["reveal", row, col] => Some(Action::Reveal(at(row)?, at(col)?)),
+        ["flag", row, col] => Some(Action::Flag(at(row)?, at(col)?)),
+        ["chord", row, col] => Some(Action::Chord(at(row)?, at(col)?)),
+        ["undo"] => Some(Action::Undo),
+        ["redo"] => Some(Action::Redo),
+        // A session records a restart as a new replay, never as a step, and
+        // replaying one here would draw boards nobody played.
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::config::{FirstClick, Generation};
+
+    fn config() -> Config {
+        Config::new(9, 9, 10)
+            .expect("valid config")
+            .with_question_marks(true)
+    }
+
+    /// A session driven through a spread of actions, undo and redo included.
+    fn played() -> Session {
+        let actions = [
+            Action::Reveal(4, 4),
+            Action::Flag(0, 0),
+            Action::Flag(0, 0),
+            Action::Reveal(8, 8),
+            Action::Undo,
+            Action::Reveal(0, 8),
+            Action::Chord(4, 4),
+            Action::Redo,
+            Action::Reveal(8, 0),
+        ];
+        let clock = ManualClock::new(1_000);
+        actions
+            .iter()
+            .fold(Session::new(config(), 7, &clock), |session, &action| {
+                let next = session.apply(action, &clock);
+                clock.advance(250);
+                next
+            })
+    }
+
+    #[test]
+    fn replaying_a_session_rebuilds_the_final_board_exactly() {
+        let session = played();
+        assert_eq!(
+            &session.replay().expect("recording").final_state(),
+            session.history().present()
+        );
+    }
+
+    #[test]
+    fn every_intermediate_state_is_rebuilt_in_order() {
+        let session = played();
+        let mut rng = Generator::new(7);
+        let mut history = History::new(config());
+        let states = session.replay().expect("recording").states();
+        assert_eq!(
+            states.len(),
+            session.replay().expect("recording").steps.len() + 1
+        );
+        for (step, state) in session
+            .replay()
+            .expect("recording")
+            .steps
+            .iter()
+            .zip(&states[1..])
+        {
+            history = history.apply(step.action, &mut rng);
+            assert_eq!(history.present(), state);
+        }
+    }
+
+    #[test]
+    fn a_share_code_opens_the_same_board_in_another_session() {
+        let clock = ManualClock::new(0);
+        let session = played();
+        let code = session.replay().expect("recording").code().to_string();
+        let shared = Session::from_code(code.parse().expect("valid code"), &clock)
+            .apply(Action::Reveal(4, 4), &clock);
+        let mines = |session: &Session| {
+            let board = &session.history().present().board;
+            board
+                .rows()
+                .flatten()
+                .map(|cell| cell.content.is_mine())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(mines(&shared), mines(&session));
+    }
+
+    #[test]
+    fn timestamps_are_relative_to_the_start_of_the_game() {
+        let session = played();
+        let steps = &session.replay().expect("recording").steps;
+        assert_eq!(steps[0].at, 0);
+        assert_eq!(steps[3].at, 750);
+    }
+
+    #[test]
+    fn the_text_format_round_trips() {
+        let replay = played().replay().expect("recording").clone();
+        let text = replay.to_string();
+        assert!(text.starts_with("minesweeper-replay 2\n"));
+        assert!(text.contains("\ngenerator 1\n"));
+        assert!(text.contains("\n250 flag 0 0\n"));
+        assert_eq!(text.parse::<Replay>(), Ok(replay));
+    }
+
+    #[test]
+    fn a_parsed_replay_reproduces_the_recorded_game() {
+        let session = played();
+        let parsed: Replay = session
+            .replay()
+            .expect("recording")
+            .to_string()
+            .parse()
+            .expect("round trip");
+        assert_eq!(&parsed.final_state(), session.history().present());
+    }
+
+    #[test]
+    fn a_restart_starts_a_new_recording_determined_by_the_old_seed() {
+        let clock = ManualClock::new(9_000);
+        let first = played().apply(Action::Restart, &clock);
+        let second = played().apply(Action::Restart, &clock);
+        assert!(first.replay().expect("recording").steps.is_empty());
+        assert_eq!(
+            first.replay().expect("recording").seed,
+            second.replay().expect("recording").seed
+        );
+    }
+
+    #[test]
+    fn a_reveal_no_layout_serves_is_reported_and_not_recorded() {
+        let clock = ManualClock::new(0);
+        // Past the config check: the one mine sits beside the click, a 50/50.
+        let config = Config::new(3, 1, 0)
+            .expect("valid config")
+            .with_first_click(FirstClick::SafeCell)
+            .with_generation(Generation::NoGuess);
+        let session = Session::new(Config { mines: 1, ..config }, 3, &clock);
+        let next = session.apply(Action::Reveal(0, 1), &clock);
+        assert_eq!(next.failure(), Some(NoGuessError { row: 0, col: 1 }));
+        assert_eq!(next.history().present(), session.history().present());
+        assert!(next.replay().expect("recording").steps.is_empty());
+        assert_eq!(next.apply(Action::Flag(0, 0), &clock).failure(), None);
+    }
+
+    #[test]
+    fn a_game_after_a_restart_round_trips_and_a_recorded_restart_is_refused() {
+        let clock = ManualClock::new(9_000);
+        let session = played()
+            .apply(Action::Restart, &clock)
+            .apply(Action::Reveal(4, 4), &clock)
+            .apply(Action::Flag(0, 0), &clock);
+        let replay = session.replay().expect("recording");
+        assert!(replay
+            .steps
+            .iter()
+            .all(|step| step.action != Action::Restart));
+        let parsed: Replay = replay.to_string().parse().expect("round trip");
+        assert_eq!(&parsed, replay);
+        assert_eq!(&parsed.final_state(), session.history().present());
+        let text = replay.to_string().replace("0 reveal 4 4", "0 restart");
+        assert_eq!(
+            text.parse::<Replay>(),
+            Err(ReplayError::BadLine {
+                line: 17,
+                text: "0 restart".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn a_resumed_game_plays_on_unrecorded_until_the_next_restart() {
+        let clock = ManualClock::new(0);
+        let saved = played().history().present().clone();
+        let resumed = Session::resume(saved.clone(), 5, &clock).apply(Action::Flag(0, 1), &clock);
+        assert!(resumed.replay().is_none());
+        assert_ne!(resumed.history().present(), &saved);
+        assert!(resumed.apply(Action::Restart, &clock).replay().is_some());
+    }
+
+    #[test]
+    fn the_session_clock_runs_from_the_opening_reveal() {
+        let clock = ManualClock::new(0);
+        let session = Session::new(config(), 7, &clock).apply(Action::Flag(0, 0), &clock);
+        clock.advance(5_000);
+        assert_eq!(session.elapsed(&clock), 0);
+        let session = session.apply(Action::Reveal(4, 4), &clock);
+        clock.advance(1_200);
+        assert!(session.stopwatch().is_running());
+        assert_eq!(session.elapsed(&clock), 1_200);
+        let restarted = session.apply(Action::Restart, &clock);
+        assert_eq!(restarted.elapsed(&clock), 0);
+    }
+
+    #[test]
+    fn malformed_replays_are_rejected_with_a_typed_error() {
+        assert_eq!("".parse::<Replay>(), Err(ReplayError::MissingHeader));
+        assert_eq!(
+            "minesweeper-replay 1\n".parse::<Replay>(),
+            Err(ReplayError::UnsupportedVersion("1".to_owned()))
+        );
+        let text = played().replay().expect("recording").to_string();
+        assert_eq!(
+            text.replace("seed 7\n", "").parse::<Replay>(),
+            Err(ReplayError::MissingField("seed"))
+        );
+        assert_eq!(
+            text.replace("generator 1\n", "").parse::<Replay>(),
+            Err(ReplayError::MissingField("generator"))
+        );
+        assert_eq!(
+            text.replace("generator 1", "generator 9").parse::<Replay>(),
+            Err(ReplayError::BadLine {
+                line: 16,
+                text: "generator 9".to_owned(),
+            })
+        );
+        assert_eq!(
+            text.replace("250 flag 0 0", "250 dig 0 0")
+                .parse::<Replay>(),
+            Err(ReplayError::BadLine {
+                line: 18,
+                text: "250 dig 0 0".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn a_replay_of_an_impossible_board_fails_config_validation() {
+        let text = played()
+            .replay()
+            .expect("recording")
+            .to_string()
+            .replace("mines 10", "mines 80");
+        assert!(matches!(
+            text.parse::<Replay>(),
+            Err(ReplayError::Config(ConfigError::TooManyMines { .. }))
+        ));
+    }
+}
@@ -0,0 +1,785 @@
+//! Saved games: a [`GameState`] as versioned, human-readable text.
+//!
+//! The settings lines are those of a replay, followed by whether the mines
+//! have been laid, the result, and a picture of the board with one glyph per
+//! cell. Upper case always means a mine:
+//!
+//! | glyph       | cell                                  |
+//! |-------------|---------------------------------------|
+//! | `.` / `*`   | covered, unmarked, safe / mine        |
+//! | `f` / `F`   | flagged, safe / mine                  |
+//! | `q` / `Q`   | question-marked, safe / mine          |
+//! | `0`..=`9`   | uncovered number (checked on load)    |
+//! | `+`         | uncovered number of 10 or more        |
+//! | `X`         | uncovered mine                        |
+//! | `-`         | gap in the mask, not a cell           |
+//!
+//! Only a cube's cells, a wide kernel's or a stack's neighbours can touch
+//! ten mines or more. A cube's layers are drawn top first, with a blank line
+//! between them.
+//!
+//! A board that stacks mines follows the picture with two more of the same
+//! shape: `mines`, giving how many each mine cell holds, and `flags`, giving
+//! how many each flag claims, with `.` for a cell that has none.
+//!
+//! ```text
+//! minesweeper-save 2
+//! width 4
+//! height 3
+//! depth 1
+//! mines 2
+//! mines-per-cell 1
+//! lives 1
+//! question-marks false
+//! generation uniform
+//! first-click opening
+//! undo loss-is-final
+//! topology flat
+//! grid square
+//! kernel moore
+//! seeded true
+//! result playing
+//! board
+//! 001.
+//! 012F
+//! 01*.
+//! ```
+//!
+//! Version 2 added the `depth`, `mines-per-cell`, `lives`, `first-click`,
+//! `topology`, `grid`, `kernel` and `mask` lines, the `+` and `-` glyphs and
+//! the count pictures. A version 1 file has none of them and loads with the
+//! values every version 1 board had. The version goes up again whenever a
+//! file gains something an older reader would refuse.
+//!
+//! Loading checks the file against the same rules the game plays by, so a
+//! shared or hand-edited save can only restore a position the game could
+//! have reached.
+
+use std::fmt;
+
+use crate::config::{Config, ConfigError, Mask};
+use crate::format::{self, HeaderError, Settings, SettingsError};
+use crate::game::{Board, Cell, CellState, GameResult, GameState, Mark};
+
+const HEADER: &str = "minesweeper-save";
+const VERSION: u32 = 2;
+
+/// The oldest version [`read`] still loads.
+const OLDEST_VERSION: u32 = 1;
+
+/// What `+` claims: the number is at least this, and too wide for one digit.
+const TEN_OR_MORE: u16 = 10;
+
+/// Titles of a stacked board's count pictures.
+const MINES: &str = "mines";
+const FLAGS: &str = "flags";
+
+/// Numbered picture lines, as [`format::numbered_lines`] yields them.
+type Lines<'a> = [(usize, &'a str)];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SaveError {
+    MissingHeader,
+    UnsupportedVersion(String),
+    MissingField(&'static str),
+    /// 1-based line number and the offending text.
+    BadLine {
+        line: usize,
+        text: String,
+    },
+    Config(ConfigError),
+    RowCount {
+        expected: usize,
+        found: usize,
+    },
+    RowWidth {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    UnknownGlyph {
+        line: usize,
+        glyph: char,
+    },
+    /// A gap drawn where the mask has a cell, or a cell where it has a gap.
+    /// `column` counts from 1.
+    OffMask {
+        line: usize,
+        column: usize,
+    },
+    /// A count for a cell with no mine or flag to count, none for one that
+    /// has, or more than the config stacks.
+    BadCount {
+        line: usize,
+        column: usize,
+    },
+    /// An uncovered number that disagrees with the mines around it.
+    WrongNumber {
+        row: usize,
+        col: usize,
+        shown: u16,
+        actual: u16,
+    },
+    MineCount {
+        expected: usize,
+        found: usize,
+    },
+    /// The board and result could not both be true, e.g. a win with safe cells
+    /// still covered.
+    Impossible(&'static str),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingHeader => write!(f, "not a saved game: expected {HEADER:?} first"),
+            Self::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "save version {version} is not supported (expected \
+                     {OLDEST_VERSION} to {VERSION})"
+                )
+            }
+            Self::MissingField(key) => write!(f, "save has no {key:?} line"),
+            Self::BadLine { line, text } => write!(f, "line {line} is not understood: {text:?}"),
+            Self::Config(error) => write!(f, "save describes an invalid board: {error}"),
+            Self::RowCount { expected, found } => {
+                write!(f, "board has {found} rows, expected {expected}")
+            }
+            Self::RowWidth {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line} has {found} cells, expected {expected}"),
+            Self::UnknownGlyph { line, glyph } => {
+                write!(f, "line {line} holds {glyph:?}, which is not a cell")
+            }
+            Self::OffMask { line, column } => write!(
+                f,
+                "line {line}, column {column} does not follow the mask: gaps and \
+                 only gaps are drawn as {:?}",
+                Mask::GAP
+            ),
+            Self::BadCount { line, column } => write!(
+                f,
+                "line {line}, column {column} gives a count that does not match \
+                 the board picture"
+            ),
+            Self::WrongNumber {
+                row,
+                col,
+                shown,
+                actual,
+            } => write!(
+                f,
+                "cell ({row}, {col}) shows {shown} but touches {actual} mines"
+            ),
+            Self::MineCount { expected, found } => {
+                write!(f, "board holds {found} mines, expected {expected}")
+            }
+            Self::Impossible(why) => write!(f, "impossible position: {why}"),
+        }
+    }
+}
+
+pub fn write(state: &GameState) -> String {
+    Save(state).to_string()
+}
+
+pub fn read(text: &str) -> Result<GameState, SaveError> {
+    let mut lines = format::numbered_lines(text);
+    format::expect_header(&mut lines, HEADER, OLDEST_VERSION..=VERSION).map_err(
+        |error| match error {
+            HeaderError::Missing => SaveError::MissingHeader,
+            HeaderError::Unsupported(version) => SaveError::UnsupportedVersion(version),
+        },
+    )?;
+    let mut settings = Settings::default();
+    let mut seeded = None;
+    let mut result = None;
+    for (line, text) in lines.by_ref() {
+        let bad = || SaveError::BadLine {
+            line,
+            text: text.to_owned(),
+        };
+        match text.split_once(' ') {
+            _ if text == "board" => break,
+            Some(("seeded", value)) => seeded = Some(value.trim().parse().map_err(|_| bad())?),
+            Some(("result", value)) => result = Some(parse_result(value.trim()).ok_or_else(bad)?),
+            Some((key, value)) => settings.set(key, value.trim()).ok_or_else(bad)?,
+            None => return Err(bad()),
+        }
+    }
+    let config = settings.config().map_err(|error| match error {
+        SettingsError::Missing(key) => SaveError::MissingField(key),
+        SettingsError::Invalid(error) => SaveError::Config(error),
+    })?;
+    let seeded = seeded.ok_or(SaveError::MissingField("seeded"))?;
+    let result = result.ok_or(SaveError::MissingField("result"))?;
+    let rest: Vec<(usize, &str)> = lines.collect();
+    let (rows, counts) = sections(&config, &rest)?;
+    let (mut cells, shown) = parse_board(&config, rows)?;
+    if let Some((mines, flags)) = counts {
+        read_counts(&config, mines, &mut cells, |cell, count| {
+            match (cell.content.is_mine(), count) {
+                (true, 1..) => cell.content = Cell::Mine(count),
+                (false, 0) => {}
+                _ => return false,
+            }
+            true
+        })?;
+        read_counts(&config, flags, &mut cells, |cell, count| {
+            match (cell.is_flagged(), count) {
+                (true, 1..) => cell.mark = Mark::Flag(count),
+                (false, 0) => {}
+                _ => return false,
+            }
+            true
+        })?;
+    }
+    let board = Board::restore(config, cells, seeded);
+    check(&board, &shown, result)?;
+    Ok(GameState { board, result })
+}
+
+/// Display adapter so the text is written in one pass.
+struct Save<'a>(&'a GameState);
+
+impl fmt::Display for Save<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let GameState { board, result } = self.0;
+        writeln!(f, "{HEADER} {VERSION}")?;
+        format::write_settings(f, board.config())?;
+        writeln!(f, "seeded {}", board.is_seeded())?;
+        writeln!(f, "result {}", result_name(*result))?;
+        writeln!(f, "board")?;
+        picture(f, board, glyph)?;
+        if board.config().mines_per_cell > 1 {
+            writeln!(f, "{MINES}")?;
+            picture(f, board, |cell| count_glyph(cell.content.mines()))?;
+            writeln!(f, "{FLAGS}")?;
+            picture(f, board, |cell| count_glyph(cell.flags()))?;
+        }
+        Ok(())
+    }
+}
+
+/// One line per stored row, gaps drawn as gaps and layers set apart.
+fn picture(
+    f: &mut fmt::Formatter<'_>,
+    board: &Board,
+    glyph: impl Fn(&CellState) -> char,
+) -> fmt::Result {
+    let height = board.config().height;
+    for (row, cells) in board.rows().enumerate() {
+        if row > 0 && row % height == 0 {
+            writeln!(f)?;
+        }
+        let line: String = cells
+            .iter()
+            .enumerate()
+            .map(|(col, cell)| match board.contains(row, col) {
+                true => glyph(cell),
+                false => Mask::GAP,
+            })
+            .collect();
+        writeln!(f, "{line}")?;
+    }
+    Ok(())
+}
+
+/// A stack size is at most nine, so one digit; nothing to count is `.`.
+fn count_glyph(count: usize) -> char {
+    match count {
+        0 => '.',
+        count => char::from(b'0' + count as u8),
+    }
+}
+
+fn glyph(cell: &CellState) -> char {
+    let mine = cell.content.is_mine();
+    match (cell.uncovered, cell.mark, cell.content) {
+        (true, _, Cell::Mine(_)) => 'X',
+        (true, _, Cell::Adjacent(count)) if count >= TEN_OR_MORE => '+',
+        (true, _, Cell::Adjacent(count)) => char::from(b'0' + count as u8),
+        (false, Mark::None, _) if mine => '*',
+        (false, Mark::None, _) => '.',
+        (false, Mark::Flag(_), _) if mine => 'F',
+        (false, Mark::Flag(_), _) => 'f',
+        (false, Mark::Question, _) if mine => 'Q',
+        (false, Mark::Question, _) => 'q',
+    }
+}
+
+/// The cell a glyph stands for, plus the number it claims if it shows one.
+/// Mines and flags count one until a stacked board's count pictures say more.
+fn cell(glyph: char) -> Option<(CellState, Option<u16>)> {
+    let (mine, uncovered, mark) = match glyph {
+        '.' => (false, false, Mark::None),
+        '*' => (true, false, Mark::None),
+        'f' => (false, false, Mark::Flag(1)),
+        'F' => (true, false, Mark::Flag(1)),
+        'q' => (false, false, Mark::Question),
+        'Q' => (true, false, Mark::Question),
+        'X' => (true, true, Mark::None),
+        '0'..='9' | '+' => {
+            let shown = match glyph {
+                '+' => TEN_OR_MORE,
+                digit => u16::from(digit as u8 - b'0'),
+            };
+            let cell = CellState {
+                uncovered: true,
+                ..CellState::default()
+            };
+            return Some((cell, Some(shown)));
+        }
+        _ => return None,
+    };
+    let content = match mine {
+        true => Cell::Mine(1),
+        false => Cell::default(),
+    };
+    let cell = CellState {
+        content,
+        uncovered,
+        mark,
+    };
+    Some((cell, None))
+}
+
+/// The board picture and, for a stacked board, the mine and flag count
+/// pictures after it.
+#[allow(clippy::type_complexity)]
+fn sections<'a, 'b>(
+    config: &Config,
+    lines: &'b Lines<'a>,
+) -> Result<(&'b Lines<'a>, Option<(&'b Lines<'a>, &'b Lines<'a>)>), SaveError> {
+    if config.mines_per_cell == 1 {
+        return Ok((lines, None));
+    }
+    let find = |title: &'static str| {
+        lines
+            .iter()
+            .position(|&(_, text)| text == title)
+            .ok_or(SaveError::MissingField(title))
+    };
+    let (mines, flags) = (find(MINES)?, find(FLAGS)?);
+    if flags < mines {
+        return Err(SaveError::BadLine {
+            line: lines[flags].0,
+            text: FLAGS.to_owned(),
+        });
+    }
+    let counts = (&lines[mines + 1..flags], &lines[flags + 1..]);
+    Ok((&lines[..mines], Some(counts)))
+}
+
+/// Each slot's glyph with its line and 1-based column, `None` for a gap, once
+/// the picture is known to have the board's rows, widths and gaps.
+fn glyphs(config: &Config, rows: &Lines) -> Result<Vec<(usize, usize, Option<char>)>, SaveError> {
+    if rows.len() != config.rows() {
+        return Err(SaveError::RowCount {
+            expected: config.rows(),
+            found: rows.len(),
+        });
+    }
+    let mut glyphs = Vec::with_capacity(config.area());
+    for (row, &(line, text)) in rows.iter().enumerate() {
+        let found = text.chars().count();
+        if found != config.width {
+            return Err(SaveError::RowWidth {
+                line,
+                expected: config.width,
+                found,
+            });
+        }
+        for (col, glyph) in text.chars().enumerate() {
+            let column = col + 1;
+            if (glyph == Mask::GAP) == config.contains(row, col) {
+                return Err(SaveError::OffMask { line, column });
+            }
+            glyphs.push((line, column, (glyph != Mask::GAP).then_some(glyph)));
+        }
+    }
+    Ok(glyphs)
+}
+
+fn parse_board(
+    config: &Config,
+    rows: &Lines,
+) -> Result<(Vec<CellState>, Vec<Option<u16>>), SaveError> {
+    let mut cells = Vec::with_capacity(config.area());
+    let mut shown = Vec::with_capacity(config.area());
+    for (line, _, glyph) in glyphs(config, rows)? {
+        let (state, number) = match glyph {
+            None => (CellState::default(), None),
+            Some(glyph) => cell(glyph).ok_or(SaveError::UnknownGlyph { line, glyph })?,
+        };
+        cells.push(state);
+        shown.push(number);
+    }
+    Ok((cells, shown))
+}
+
+/// Reads a count picture onto `cells` through `set`, which refuses a count
+/// that does not fit the cell.
+fn read_counts(
+    config: &Config,
+    rows: &Lines,
+    cells: &mut [CellState],
+    mut set: impl FnMut(&mut CellState, u8) -> bool,
+) -> Result<(), SaveError> {
+    for (index, (line, column, glyph)) in glyphs(config, rows)?.into_iter().enumerate() {
+        let count = match glyph {
+            None => continue,
+            Some('.') => 0,
+            Some(digit @ '1'..='9') => digit as u8 - b'0',
+            Some(glyph) => return Err(SaveError::UnknownGlyph { line, glyph }),
+        };
+        if count as usize > config.mines_per_cell || !set(&mut cells[index], count) {
+            return Err(SaveError::BadCount { line, column });
+        }
+    }
+    Ok(())
+}
+
+/// Holds the restored board to the rules that produced the original.
+fn check(
+    board: &Board,
+    shown: &[Option<u16>],
+    result: Option<GameResult>,
+) -> Result<(), SaveError> {
+    let config = board.config();
+    let cells: Vec<CellState> = board.rows().flatten().copied().collect();
+    for (index, (cell, shown)) in cells.iter().zip(shown).enumerate() {
+        let (Some(shown), Cell::Adjacent(actual)) = (*shown, cell.content) else {
+            continue;
+        };
+        if shown != actual.min(TEN_OR_MORE) {
+            let (row, col) = (index / config.width, index % config.width);
+            return Err(SaveError::WrongNumber {
+                row,
+                col,
+                shown,
+                actual,
+            });
+        }
+    }
+    let mines: usize = cells.iter().map(|c| c.content.mines()).sum();
+    let uncovered = cells.iter().any(|c| c.uncovered);
+    let hits = cells.iter().filter(|c| c.is_exploded()).count();
+    if !board.is_seeded() {
+        return match (mines, uncovered, result) {
+            (0, false, None) => Ok(()),
+            _ => Err(SaveError::Impossible(
+                "mines are laid by the first reveal, so an unseeded board is untouched",
+            )),
+        };
+    }
+    if mines != config.mines {
+        return Err(SaveError::MineCount {
+            expected: config.mines,
+            found: mines,
+        });
+    }
+    match result {
+        Some(GameResult::Lost) if hits == 0 => Err(SaveError::Impossible(
+            "a lost game shows the mine that ended it",
+        )),
+        Some(GameResult::Won) | None if hits >= config.lives => Err(SaveError::Impossible(
+            "the mine that spends the last life ends the game",
+        )),
+        Some(GameResult::Won) if !board.is_cleared() => Err(SaveError::Impossible(
+            "a won game has every safe cell uncovered",
+        )),
+        None if board.is_cleared() => Err(SaveError::Impossible(
+            "a game with every safe cell uncovered is already won",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn result_name(result: Option<GameResult>) -> &'static str {
+    match result {
+        None => "playing",
+        Some(GameResult::Won) => "won",
+        Some(GameResult::Lost) => "lost",
+    }
+}
+
+fn parse_result(text: &str) -> Option<Option<GameResult>> {
+    match text {
+        "playing" => Some(None),
+        "won" => Some(Some(GameResult::Won)),
+        "lost" => Some(Some(GameResult::Lost)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::{cube, layout, layout_with};
+    use crate::game::Action;
+    use crate::generator::Generator;
+
+    const SAVED: &str = "\
+minesweeper-save 2
+width 4
+height 3
+depth 1
+mines 2
+mines-per-cell 1
+lives 1
+question-marks true
+generation uniform
+first-click opening
+undo loss-is-final
+topology flat
+grid square
+kernel moore
+seeded true
+result playing
+board
+001.
+012F
+01*q
+";
+
+    fn played() -> GameState {
+        let board = layout_with(&["....", "...*", "..*."], |config| {
+            config.with_question_marks(true)
+        });
+        let mut rng = Generator::new(1);
+        let state = GameState {
+            board,
+            result: None,
+        };
+        [
+            Action::Reveal(0, 0),
+            Action::Flag(1, 3),
+            Action::Flag(2, 3),
+            Action::Flag(2, 3),
+        ]
+        .into_iter()
+        .fold(state, |state, action| state.apply(action, &mut rng))
+    }
+
+    #[test]
+    fn a_game_in_progress_round_trips() {
+        let state = played();
+        assert_eq!(read(&write(&state)), Ok(state));
+    }
+
+    #[test]
+    fn the_text_is_the_documented_picture() {
+        assert_eq!(write(&played()), SAVED);
+    }
+
+    #[test]
+    fn a_cube_saves_layer_by_layer_with_wide_numbers_as_plus() {
+        let full = ["****", "****", "****", "****"];
+        let empty = ["....", "....", "....", "...."];
+        let mut board = cube(&[&full, &["*...", "....", "....", "...."], &empty, &empty]);
+        let result = board.reveal(5, 1, &mut Generator::new(0));
+        let state = GameState { board, result };
+        let text = write(&state);
+        assert!(text.contains("depth 4\n"));
+        assert!(text.contains("\n****\n\n*...\n.+..\n"));
+        assert_eq!(read(&text), Ok(state));
+        assert!(matches!(
+            read(&text.replace(".+..", ".9..")),
+            Err(SaveError::WrongNumber {
+                shown: 9,
+                actual: 10,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn a_shaped_board_saves_its_mask_and_draws_the_gaps() {
+        let mut board = layout(&["--..", "*...", "...."]);
+        let result = board.reveal(2, 3, &mut Generator::new(0));
+        let state = GameState { board, result };
+        let text = write(&state);
+        assert!(text.contains("mask --## #### ####\n"));
+        assert!(text.contains("board\n--00\n*100\n.100\n"));
+        assert_eq!(read(&text), Ok(state));
+        assert_eq!(
+            read(&text.replace("--00", "-.00")),
+            Err(SaveError::OffMask {
+                line: 19,
+                column: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn a_stacked_board_saves_its_mine_and_flag_counts() {
+        let mut board = layout(&["3...", "....", "...2"]);
+        board.toggle_flag(0, 0);
+        board.toggle_flag(0, 0);
+        let result = board.reveal(0, 3, &mut Generator::new(0));
+        let state = GameState { board, result };
+        let text = write(&state);
+        assert!(text.contains("mines-per-cell 3\n"));
+        assert!(text.ends_with("mines\n3...\n....\n...2\nflags\n2...\n....\n....\n"));
+        assert_eq!(read(&text), Ok(state));
+        assert!(matches!(
+            read(&text.replace("flags\n2...", "flags\n2..1")),
+            Err(SaveError::BadCount { column: 4, .. })
+        ));
+        assert_eq!(
+            read(&text.replace("\nmines\n", "\n")),
+            Err(SaveError::MissingField("mines"))
+        );
+    }
+
+    #[test]
+    fn a_version_1_save_from_before_the_later_settings_still_loads() {
+        let version_1 = "\
+minesweeper-save 1
+width 4
+height 3
+mines 2
+question-marks true
+generation uniform
+undo loss-is-final
+seeded true
+result playing
+board
+001.
+012F
+01*q
+";
+        assert_eq!(read(version_1), Ok(played()));
+        assert!(write(&played()).starts_with("minesweeper-save 2\n"));
+    }
+
+    #[test]
+    fn a_save_without_a_topology_line_is_a_flat_board() {
+        let older = SAVED.replace("topology flat\n", "");
+        assert_eq!(read(&older), Ok(played()));
+        // On a torus the corner blank touches the mine across the edge.
+        let torus = SAVED.replace("topology flat", "topology torus");
+        assert!(matches!(
+            read(&torus),
+            Err(SaveError::WrongNumber { row: 0, col: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn an_unseeded_board_round_trips_and_still_seeds_on_the_first_reveal() {
+        let config = Config::new(5, 5, 4).expect("valid config");
+        let fresh = GameState::new(config);
+        let restored = read(&write(&fresh)).expect("round trip");
+        assert_eq!(restored, fresh);
+        assert!(!restored.board.is_seeded());
+    }
+
+    #[test]
+    fn finished_games_round_trip() {
+        let mut rng = Generator::new(1);
+        let lost = played().apply(Action::Reveal(2, 2), &mut rng);
+        assert_eq!(lost.result, Some(GameResult::Lost));
+        assert_eq!(read(&write(&lost)), Ok(lost));
+    }
+
+    #[test]
+    fn a_number_that_disagrees_with_the_mines_is_rejected() {
+        let text = SAVED.replace("012F", "013F");
+        assert_eq!(
+            read(&text),
+            Err(SaveError::WrongNumber {
+                row: 1,
+                col: 2,
+                shown: 3,
+                actual: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn the_board_must_hold_exactly_the_configured_mines() {
+        let text = SAVED.replace("mines 2", "mines 3");
+        assert_eq!(
+            read(&text),
+            Err(SaveError::MineCount {
+                expected: 3,
+                found: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn settings_are_validated_like_a_build_time_config() {
+        let text = SAVED.replace("width 4", "width 0");
+        assert!(matches!(
+            read(&text),
+            Err(SaveError::Config(ConfigError::ZeroDimension { .. }))
+        ));
+    }
+
+    #[test]
+    fn a_malformed_picture_names_the_line() {
+        assert_eq!(
+            read(&SAVED.replace("012F", "012")),
+            Err(SaveError::RowWidth {
+                line: 19,
+                expected: 4,
+                found: 3,
+            })
+        );
+        assert_eq!(
+            read(&SAVED.replace("012F", "012!")),
+            Err(SaveError::UnknownGlyph {
+                line: 19,
+                glyph: '!',
+            })
+        );
+        assert_eq!(
+            read(&SAVED.replace("01*q\n", "")),
+            Err(SaveError::RowCount {
+                expected: 3,
+                found: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn a_result_the_board_contradicts_is_rejected() {
+        assert!(matches!(
+            read(&SAVED.replace("result playing", "result won")),
+            Err(SaveError::Impossible(_))
+        ));
+        assert!(matches!(
+            read(&SAVED.replace("result playing", "result lost")),
+            Err(SaveError::Impossible(_))
+        ));
+        assert!(matches!(
+            read(&SAVED.replace("seeded true", "seeded false")),
+            Err(SaveError::Impossible(_))
+        ));
+    }
+
+    #[test]
+    fn a_mine_set_off_with_a_life_to_spare_stays_uncovered_in_play() {
+        let exploded = SAVED.replace("01*q", "01Xq");
+        assert!(matches!(read(&exploded), Err(SaveError::Impossible(_))));
+        let state = read(&exploded.replace("lives 1", "lives 2")).expect("one life left");
+        assert_eq!(state.result, None);
+        assert_eq!(state.board.lives_remaining(), 1);
+        assert_eq!(state.board.mines_remaining(), 0);
+        assert_eq!(read(&write(&state)), Ok(state));
+    }
+
+    #[test]
+    fn headers_are_checked_before_anything_else() {
+        assert_eq!(read("width 4"), Err(SaveError::MissingHeader));
+        assert_eq!(
+            read(&SAVED.replace("minesweeper-save 2", "minesweeper-save 7")),
+            Err(SaveError::UnsupportedVersion("7".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,468 @@
+//! Share codes: one short, URL-safe word that names a board.
+//!
+//! A code carries the config, the seed and the [`Version`] of the generator,
+//! which is everything [`Board`](crate::game::Board) needs to lay the same
+//! mines again, so a teammate who opens it plays the exact same board. Unlike
+//! a [`Replay`](crate::replay::Replay) it records no moves.
+//!
+//! The bytes are a format number, then the seed and the settings as
+//! little-endian base-128 varints and packed flags, then a check byte, all
+//! written in URL-safe base64 without padding. A default board comes to
+//! about thirty characters; a mask adds one bit per cell of the rectangle.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::{
+    Config, ConfigError, FirstClick, Generation, Grid, Kernel, Mask, Offsets, Topology, UndoPolicy,
+};
+use crate::generator::Version;
+
+const FORMAT: u8 = 1;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Bits of the flags byte.
+const QUESTION_MARKS: u8 = 1;
+const NO_GUESS: u8 = 1 << 1;
+const UNDO_LOSSES: u8 = 1 << 2;
+const HEX: u8 = 1 << 3;
+const CYLINDER: u8 = 1 << 4;
+const TORUS: u8 = 1 << 5;
+const MASKED: u8 = 1 << 6;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShareCode {
+    pub config: Config,
+    pub seed: u64,
+    pub generator: Version,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShareCodeError {
+    /// A character outside the URL-safe base64 alphabet.
+    BadCharacter(char),
+    /// The check byte disagrees with the rest: most likely a typo.
+    Checksum,
+    UnsupportedFormat(u8),
+    UnknownGenerator(u64),
+    /// Too short for the board it starts to describe, a value out of range,
+    /// or bytes left over after it.
+    Malformed,
+    Config(ConfigError),
+}
+
+impl fmt::Display for ShareCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadCharacter(glyph) => write!(f, "{glyph:?} cannot appear in a share code"),
+            Self::Checksum => write!(f, "the share code does not check out; is it mistyped?"),
+            Self::UnsupportedFormat(format) => write!(
+                f,
+                "share code format {format} is not supported (expected {FORMAT})"
+            ),
+            Self::UnknownGenerator(version) => {
+                write!(f, "share code needs layout generator {version}")
+            }
+            Self::Malformed => write!(f, "the share code is cut short or garbled"),
+            Self::Config(error) => write!(f, "share code describes an invalid board: {error}"),
+        }
+    }
+}
+
+impl fmt::Display for ShareCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = vec![FORMAT];
+        let config = &self.config;
+        put_varint(&mut bytes, self.generator.number().into());
+        put_varint(&mut bytes, self.seed);
+        for value in [
+            config.width,
+            config.height,
+            config.depth,
+            config.mines,
+            config.mines_per_cell,
+            config.lives,
+        ] {
+            put_varint(&mut bytes, value as u64);
+        }
+        bytes.push(flags(config));
+        bytes.push(match config.first_click {
+            FirstClick::Unprotected => 0,
+            FirstClick::SafeCell => 1,
+            FirstClick::Opening => 2,
+            FirstClick::Relocate => 3,
+        });
+        put_kernel(&mut bytes, config.kernel);
+        if let Some(mask) = &config.mask {
+            put_mask(&mut bytes, mask);
+        }
+        bytes.push(checksum(&bytes));
+        f.write_str(&encode(&bytes))
+    }
+}
+
+impl FromStr for ShareCode {
+    type Err = ShareCodeError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut bytes = decode(text.trim())?;
+        let check = bytes.pop().ok_or(ShareCodeError::Malformed)?;
+        if checksum(&bytes) != check {
+            return Err(ShareCodeError::Checksum);
+        }
+        let mut reader = Reader(&bytes);
+        let format = reader.byte()?;
+        if format != FORMAT {
+            return Err(ShareCodeError::UnsupportedFormat(format));
+        }
+        let number = reader.varint()?;
+        let generator = u32::try_from(number)
+            .ok()
+            .and_then(Version::from_number)
+            .ok_or(ShareCodeError::UnknownGenerator(number))?;
+        let seed = reader.varint()?;
+        let [width, height, depth, mines, mines_per_cell, lives] = [(); 6].map(|()| reader.size());
+        let flags = reader.byte()?;
+        let first_click = match reader.byte()? {
+            0 => FirstClick::Unprotected,
+            1 => FirstClick::SafeCell,
+            2 => FirstClick::Opening,
+            3 => FirstClick::Relocate,
+            _ => return Err(ShareCodeError::Malformed),
+        };
+        let kernel = reader.kernel()?;
+        let (width, height, depth) = (width?, height?, depth?);
+        let mask = match flags & MASKED {
+            0 => None,
+            _ => {
+                let rows = height.checked_mul(depth).ok_or(ShareCodeError::Malformed)?;
+                Some(reader.mask(width, rows)?)
+            }
+        };
+        if !reader.0.is_empty() {
+            return Err(ShareCodeError::Malformed);
+        }
+        let topology = match (flags & CYLINDER != 0, flags & TORUS != 0) {
+            (false, false) => Topology::Flat,
+            (true, false) => Topology::Cylinder,
+            (false, true) => Topology::Torus,
+            (true, true) => return Err(ShareCodeError::Malformed),
+        };
+        // Mines are checked last, once the shape that decides how many fit is
+        // known.
+        let config = Config::new(width, height, 0).map_err(ShareCodeError::Config)?;
+        let config = Config {
+            mines: mines?,
+            ..config
+        }
+        .with_question_marks(flags & QUESTION_MARKS != 0)
+        .with_generation(match flags & NO_GUESS {
+            0 => Generation::Uniform,
+            _ => Generation::NoGuess,
+        })
+        .with_first_click(first_click)
+        .with_undo(match flags & UNDO_LOSSES {
+            0 => UndoPolicy::LossIsFinal,
+            _ => UndoPolicy::AllowAfterLoss,
+        })
+        .with_topology(topology)
+        .with_grid(match flags & HEX {
+            0 => Grid::Square,
+            _ => Grid::Hex,
+        })
+        .with_kernel(kernel)
+        .with_depth(depth)
+        .with_mask(mask)
+        .with_mines_per_cell(mines_per_cell?)
+        .with_lives(lives?)
+        .check()
+        .map_err(ShareCodeError::Config)?;
+        Ok(Self {
+            config,
+            seed,
+            generator,
+        })
+    }
+}
+
+fn flags(config: &Config) -> u8 {
+    let mut flags = 0;
+    for (set, bit) in [
+        (config.question_marks, QUESTION_MARKS),
+        (config.generation == Generation::NoGuess, NO_GUESS),
+        (config.undo == UndoPolicy::AllowAfterLoss, UNDO_LOSSES),
+        (config.grid == Grid::Hex, HEX),
+        (config.topology == Topology::Cylinder, CYLINDER),
+        (config.topology == Topology::Torus, TORUS),
+        (config.mask.is_some(), MASKED),
+    ] {
+        if set {
+            flags |= bit;
+        }
+    }
+    flags
+}
+
+/// A tag, and for a custom kernel its step count and one byte per step
+/// numbering the cell of the window it lands on.
+fn put_kernel(bytes: &mut Vec<u8>, kernel: Kernel) {
+    match kernel {
+        Kernel::Moore => bytes.push(0),
+        Kernel::Orthogonal => bytes.push(1),
+        Kernel::Knight => bytes.push(2),
+        Kernel::Radius2 => bytes.push(3),
+        Kernel::Custom(offsets) => {
+            let span = 2 * Offsets::REACH + 1;
+            let steps: Vec<u8> = offsets
+                .iter()
+                .map(|(dr, dc)| ((dr + Offsets::REACH) * span + dc + Offsets::REACH) as u8)
+                .collect();
+            bytes.push(4);
+            bytes.push(steps.len() as u8);
+            bytes.extend(steps);
+        }
+    }
+}
+
+/// One bit per cell of the rectangle, row-major, lowest bit first.
+fn put_mask(bytes: &mut Vec<u8>, mask: &Mask) {
+    let cells: Vec<bool> = (0..mask.rows())
+        .flat_map(|row| (0..mask.width()).map(move |col| mask.contains(row, col)))
+        .collect();
+    for chunk in cells.chunks(8) {
+        let byte = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |byte, (bit, &cell)| byte | (cell as u8) << bit);
+        bytes.push(byte);
+    }
+}
+
+fn put_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Order-sensitive, so swapped characters are caught as well as wrong ones.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(FORMAT, |sum, &byte| sum.rotate_left(3) ^ byte)
+}
+
+/// The bytes still to read.
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, ShareCodeError> {
+        let (&first, rest) = self.0.split_first().ok_or(ShareCodeError::Malformed)?;
+        self.0 = rest;
+        Ok(first)
+    }
+
+    fn varint(&mut self) -> Result<u64, ShareCodeError> {
+        let mut value = 0_u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ShareCodeError::Malformed)
+    }
+
+    fn size(&mut self) -> Result<usize, ShareCodeError> {
+        usize::try_from(self.varint()?).map_err(|_| ShareCodeError::Malformed)
+    }
+
+    fn kernel(&mut self) -> Result<Kernel, ShareCodeError> {
+        Ok(match self.byte()? {
+            0 => Kernel::Moore,
+            1 => Kernel::Orthogonal,
+            2 => Kernel::Knight,
+            3 => Kernel::Radius2,
+            4 => {
+                let span = 2 * Offsets::REACH + 1;
+                let count = self.byte()?;
+                let steps: Vec<(isize, isize)> = (0..count)
+                    .map(|_| {
+                        let cell = self.byte()? as isize;
+                        Ok((cell / span - Offsets::REACH, cell % span - Offsets::REACH))
+                    })
+                    .collect::<Result<_, ShareCodeError>>()?;
+                Kernel::Custom(Offsets::new(&steps).ok_or(ShareCodeError::Malformed)?)
+            }
+            _ => return Err(ShareCodeError::Malformed),
+        })
+    }
+
+    fn mask(&mut self, width: usize, rows: usize) -> Result<Mask, ShareCodeError> {
+        let cells = width.checked_mul(rows).ok_or(ShareCodeError::Malformed)?;
+        let packed = (0..cells.div_ceil(8))
+            .map(|_| self.byte())
+            .collect::<Result<Vec<u8>, _>>()?;
+        let glyph = |index: usize| match packed[index / 8] >> (index % 8) & 1 {
+            1 => Mask::CELL,
+            _ => Mask::GAP,
+        };
+        let rows: Vec<String> = (0..rows)
+            .map(|row| (0..width).map(|col| glyph(row * width + col)).collect())
+            .collect();
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        Mask::from_rows(&rows).ok_or(ShareCodeError::Malformed)
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len() * 4 / 3 + 1);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |group, (index, &byte)| {
+                group | u32::from(byte) << (16 - 8 * index)
+            });
+        for index in 0..=chunk.len() {
+            text.push(ALPHABET[(group >> (18 - 6 * index) & 0x3F) as usize] as char);
+        }
+    }
+    text
+}
+
+fn decode(text: &str) -> Result<Vec<u8>, ShareCodeError> {
+    let values = text
+        .chars()
+        .map(|glyph| {
+            ALPHABET
+                .iter()
+                .position(|&letter| letter as char == glyph)
+                .ok_or(ShareCodeError::BadCharacter(glyph))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+    let mut bytes = Vec::with_capacity(values.len() * 3 / 4);
+    for chunk in values.chunks(4) {
+        if chunk.len() == 1 {
+            return Err(ShareCodeError::Malformed);
+        }
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |group, (index, &value)| {
+                group | (value as u32) << (18 - 6 * index)
+            });
+        for index in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * index)) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MOST_CELLS;
+
+    fn code(config: Config) -> ShareCode {
+        ShareCode {
+            config,
+            seed: 0xDEAD_BEEF_CAFE,
+            generator: Version::V1,
+        }
+    }
+
+    #[test]
+    fn a_default_board_makes_a_short_code_that_reads_back() {
+        let original = code(Config::new(9, 9, 10).expect("valid config"));
+        let text = original.to_string();
+        assert!(text.len() <= 32, "{text} is too long");
+        assert!(text
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_'));
+        assert_eq!(text.parse(), Ok(original));
+    }
+
+    #[test]
+    fn every_setting_survives_the_round_trip() {
+        let ring =
+            Mask::from_rows(&["####", "#--#", "####", "#--#", "####", "####"]).expect("valid mask");
+        let config = Config::new(4, 3, 0)
+            .expect("valid config")
+            .with_depth(2)
+            .with_mask(Some(ring))
+            .with_question_marks(true)
+            .with_undo(UndoPolicy::AllowAfterLoss)
+            .with_first_click(FirstClick::Relocate)
+            .with_topology(Topology::Cylinder)
+            .with_kernel(Kernel::Custom(
+                Offsets::new(&[(0, 1), (-4, 4), (2, -3)]).expect("valid offsets"),
+            ))
+            .with_mines_per_cell(3)
+            .with_lives(2);
+        let config = Config { mines: 5, ..config }.check().expect("valid config");
+        let original = code(config);
+        assert_eq!(original.to_string().parse(), Ok(original));
+        let hex = code(
+            Config::new(8, 6, 8)
+                .expect("valid config")
+                .with_grid(Grid::Hex)
+                .with_topology(Topology::Torus)
+                .with_generation(Generation::NoGuess),
+        );
+        assert_eq!(hex.to_string().parse(), Ok(hex));
+    }
+
+    #[test]
+    fn a_mistyped_code_is_caught_rather_than_opening_another_board() {
+        let text = code(Config::new(9, 9, 10).expect("valid config")).to_string();
+        let mut typo = text.clone().into_bytes();
+        typo[6] = if typo[6] == b'A' { b'B' } else { b'A' };
+        let typo = String::from_utf8(typo).expect("ascii");
+        assert_eq!(typo.parse::<ShareCode>(), Err(ShareCodeError::Checksum));
+        assert_eq!(
+            format!("{text}!").parse::<ShareCode>(),
+            Err(ShareCodeError::BadCharacter('!'))
+        );
+        assert_eq!(
+            text[..8].parse::<ShareCode>(),
+            Err(ShareCodeError::Checksum)
+        );
+    }
+
+    #[test]
+    fn a_code_for_a_board_the_game_would_refuse_is_rejected() {
+        let mut bytes = vec![FORMAT, 1, 7, 4, 4, 1, 9, 1, 1, 0, 2, 0];
+        bytes.push(checksum(&bytes));
+        assert!(matches!(
+            encode(&bytes).parse::<ShareCode>(),
+            Err(ShareCodeError::Config(ConfigError::TooManyMines { .. }))
+        ));
+        // 2^20 by 2^20: refused by size before the capacity check counts it.
+        assert_eq!(
+            "AQEBgIBAgIBAAQEBAQACALY".parse::<ShareCode>(),
+            Err(ShareCodeError::Config(ConfigError::TooManyCells {
+                width: 1 << 20,
+                height: 1 << 20,
+                depth: 1,
+            }))
+        );
+        let oversized = code(Config {
+            width: MOST_CELLS,
+            ..Config::new(9, 9, 10).expect("valid config")
+        });
+        assert!(matches!(
+            oversized.to_string().parse::<ShareCode>(),
+            Err(ShareCodeError::Config(ConfigError::TooManyCells { .. }))
+        ));
+        let mut bytes = vec![FORMAT, 9, 7];
+        bytes.push(checksum(&bytes));
+        assert_eq!(
+            encode(&bytes).parse::<ShareCode>(),
+            Err(ShareCodeError::UnknownGenerator(9))
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Deduction over the board as the player sees it.
+//!
+//! [`deduce`] takes a [`PlayerBoard`], which holds uncovered numbers,
+//! uncovered mines and marks and nothing of what a covered cell holds, so
+//! nothing here can know more than a careful player would. A mine set off in
+//! a game with lives to spare is uncovered, and counts as found.
+//! Flags are ignored too: they are the player's guesses, not facts.
+//!
+//! Three kinds of rule are applied until none teaches anything new: a single
+//! number that is already satisfied or already saturated, a pair of numbers
+//! whose overlap forces the cells outside it (subsets, the 1-2 pattern), and
+//! the global mine count from [`Config::mines`](crate::config::Config).
+//! Every rule assumes a cell holds at most one mine, so on a config that
+//! stacks them [`deduce`] proves nothing, and such a config cannot ask for a
+//! no-guess board.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::game::{Board, PlayerBoard, PlayerView};
+use crate::generator::Version;
+
+type Position = (usize, usize);
+
+/// Covered cells that logic alone proves safe or proves mined.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Deductions {
+    pub safe: BTreeSet<Position>,
+    pub mines: BTreeSet<Position>,
+}
+
+/// "Exactly `mines` of these covered cells are mines", read off one number.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Constraint {
+    cells: Vec<Position>,
+    mines: usize,
+}
+
+impl Deductions {
+    fn is_known(&self, position: &Position) -> bool {
+        self.safe.contains(position) || self.mines.contains(position)
+    }
+
+    /// Records every cell of `cells` as a mine (`mined`) or as safe. Returns
+    /// whether anything new was learnt.
+    fn settle(&mut self, cells: &[Position], mined: bool) -> bool {
+        let target = match mined {
+            true => &mut self.mines,
+            false => &mut self.safe,
+        };
+        let mut learnt = false;
+        for &position in cells {
+            learnt |= target.insert(position);
+        }
+        learnt
+    }
+}
+
+/// Every covered cell that the visible numbers and the total mine count prove
+/// safe or prove mined. Cells in neither set are genuinely undetermined.
+/// Both sets are empty when the config stacks mines, where a number no longer
+/// says how many of its cells are mined.
+pub fn deduce(board: &PlayerBoard) -> Deductions {
+    let mut known = Deductions::default();
+    if board.config().mines_per_cell > 1 {
+        return known;
+    }
+    loop {
+        let constraints = constraints(board, &known);
+        let mut learnt = false;
+        for constraint in &constraints {
+            learnt |= match constraint.mines {
+                0 => known.settle(&constraint.cells, false),
+                n if n == constraint.cells.len() => known.settle(&constraint.cells, true),
+                _ => false,
+            };
+        }
+        for (a, b) in overlapping_pairs(&constraints) {
+            learnt |= pair(&mut known, a, b) | pair(&mut known, b, a);
+        }
+        learnt |= global(board, &mut known);
+        if !learnt {
+            return known;
+        }
+    }
+}
+
+/// Plays the board out from `start` using nothing but the deductions of
+/// `version`, and reports whether that clears it. Works on `board` in place;
+/// pass a throwaway copy. Which boards pass decides which no-guess layouts
+/// are drawn, so a stronger [`deduce`] must not change what version 1 accepts:
+/// it gets a new version and keeps the old rules for this arm.
+pub(crate) fn clears_without_guessing(
+    board: &mut Board,
+    start: Position,
+    version: Version,
+) -> bool {
+    board.flood(start.0, start.1);
+    loop {
+        if board.is_cleared() {
+            return true;
+        }
+        let safe = match version {
+            Version::V1 => deduce(&board.player_board()).safe,
+        };
+        if safe.is_empty() {
+            return false;
+        }
+        for (row, col) in safe {
+            board.flood(row, col);
+        }
+    }
+}
+
+/// One constraint per uncovered number that still touches an unresolved cell.
+fn constraints(board: &PlayerBoard, known: &Deductions) -> Vec<Constraint> {
+    let config = board.config();
+    let mut constraints = Vec::new();
+    for row in 0..config.rows() {
+        for col in 0..config.width {
+            let PlayerView::Number(count) = board.view(row, col) else {
+                continue;
+            };
+            let covered: Vec<Position> = board
+                .neighbors(row, col)
+                .filter(|&(r, c)| board.view(r, c).is_covered())
+                .collect();
+            let exploded: usize = board
+                .neighbors(row, col)
+                .map(|(r, c)| board.view(r, c).mines())
+                .sum();
+            let found = exploded + covered.iter().filter(|p| known.mines.contains(p)).count();
+            let cells: Vec<Position> = covered
+                .into_iter()
+                .filter(|position| !known.is_known(position))
+                .collect();
+            if !cells.is_empty() {
+                constraints.push(Constraint {
+                    cells,
+                    mines: (count as usize).saturating_sub(found),
+                });
+            }
+        }
+    }
+    constraints.sort_by(|a, b| a.cells.cmp(&b.cells));
+    constraints.dedup();
+    constraints
+}
+
+/// Every pair of constraints sharing at least one cell, each pair once.
+fn overlapping_pairs(constraints: &[Constraint]) -> Vec<(&Constraint, &Constraint)> {
+    let mut by_cell: BTreeMap<Position, Vec<usize>> = BTreeMap::new();
+    for (index, constraint) in constraints.iter().enumerate() {
+        for &position in &constraint.cells {
+            by_cell.entry(position).or_default().push(index);
+        }
+    }
+    let pairs: BTreeSet<(usize, usize)> = by_cell
+        .values()
+        .flat_map(|indices| {
+            indices
+                .iter()
+                .flat_map(move |&a| indices.iter().map(move |&b| (a, b)))
+                .filter(|(a, b)| a < b)
+        })
+        .collect();
+    pairs
+        .into_iter()
+        .map(|(a, b)| (&constraints[a], &constraints[b]))
+        .collect()
+}
+
+/// If `b` needs so many more mines than `a` that every cell `b` has outside
+/// `a` must be one, those cells are mines and `a`'s cells outside `b` are
+/// safe. Covers subsets and the 1-2 patterns along a wall.
+fn pair(known: &mut Deductions, a: &Constraint, b: &Constraint) -> bool {
+    let only_a: Vec<Position> = difference(&a.cells, &b.cells);
+    let only_b: Vec<Position> = difference(&b.cells, &a.cells);
+    if b.mines < a.mines || b.mines - a.mines != only_b.len() {
+        return false;
+    }
+    known.settle(&only_b, true) | known.settle(&only_a, false)
+}
+
+/// Once every mine is accounted for the rest of the board is safe, and once
+/// the unresolved cells number exactly the mines left they are all mines.
+fn global(board: &PlayerBoard, known: &mut Deductions) -> bool {
+    let config = board.config();
+    let unresolved: Vec<Position> = config
+        .positions()
+        .filter(|&(row, col)| board.view(row, col).is_covered())
+        .filter(|position| !known.is_known(position))
+        .collect();
+    if unresolved.is_empty() {
+        return false;
+    }
+    let exploded: usize = config
+        .positions()
+        .map(|(row, col)| board.view(row, col).mines())
+        .sum();
+    let left = config.mines.saturating_sub(known.mines.len() + exploded);
+    match left {
+        0 => known.settle(&unresolved, false),
+        n if n == unresolved.len() => known.settle(&unresolved, true),
+        _ => false,
+    }
+}
+
+fn difference(from: &[Position], without: &[Position]) -> Vec<Position> {
+    from.iter()
+        .filter(|position| !without.contains(position))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::layout;
+
+    #[test]
+    fn a_number_with_as_many_covered_neighbors_as_mines_marks_them_all() {
+        let board = layout(&["*oo", "ooo", "ooo"]);
+        let known = deduce(&board.player_board());
+        assert_eq!(known.mines, BTreeSet::from([(0, 0)]));
+        assert!(known.safe.is_empty());
+    }
+
+    #[test]
+    fn an_exploded_mine_counts_as_found_rather_than_hiding_among_the_covered() {
+        let board = layout(&["x.o", "ooo"]);
+        let known = deduce(&board.player_board());
+        assert_eq!(known.safe, BTreeSet::from([(0, 1)]));
+        assert!(known.mines.is_empty());
+    }
+
+    #[test]
+    fn a_stacked_board_proves_nothing_rather_than_something_wrong() {
+        // Both 2s would read as two single mines and call the safe (0,1) mined.
+        let board = layout(&["2.", "oo"]);
+        assert_eq!(board.config().mines_per_cell, 2);
+        assert_eq!(deduce(&board.player_board()), Deductions::default());
+    }
+
+    #[test]
+    fn a_satisfied_number_proves_its_other_neighbors_safe() {
+        // (0,0) pins the mine on (0,1); (1,2) is then satisfied and clears (0,2).
+        let board = layout(&["o*.", "ooo", "ooo"]);
+        let known = deduce(&board.player_board());
+        assert_eq!(known.mines, BTreeSet::from([(0, 1)]));
+        assert_eq!(known.safe, BTreeSet::from([(0, 2)]));
+    }
+
+    #[test]
+    fn the_one_two_pattern_along_a_wall_is_resolved_by_pairing_numbers() {
+        // Row 1 reads 1 2 1 under three covered cells: only the middle one is safe.
+        let board = layout(&["*.*", "ooo"]);
+        let known = deduce(&board.player_board());
+        assert_eq!(known.mines, BTreeSet::from([(0, 0), (0, 2)]));
+        assert_eq!(known.safe, BTreeSet::from([(0, 1)]));
+    }
+
+    #[test]
+    fn a_genuine_fifty_fifty_yields_nothing() {
+        let board = layout(&["*.", "oo", "oo"]);
+        assert_eq!(deduce(&board.player_board()), Deductions::default());
+    }
+
+    #[test]
+    fn once_every_mine_is_found_the_rest_of_the_board_is_safe() {
+        // (0,0) pins the only mine; no number touches the right-hand column.
+        let board = layout(&["o*..", "oo..", "oo.."]);
+        let known = deduce(&board.player_board());
+        assert_eq!(known.mines, BTreeSet::from([(0, 1)]));
+        assert!(known
+            .safe
+            .is_superset(&BTreeSet::from([(0, 3), (1, 3), (2, 3)])));
+    }
+
+    #[test]
+    fn when_the_unresolved_cells_equal_the_mines_left_they_are_all_mines() {
+        // The zeros clear the middle row; no number says which of the top row
+        // are mines, but three cells and three mines leave no choice.
+        let board = layout(&["***", "...", "ooo"]);
+        let known = deduce(&board.player_board());
+        assert_eq!(known.mines, BTreeSet::from([(0, 0), (0, 1), (0, 2)]));
+    }
+
+    #[test]
+    fn covered_cells_are_never_read() {
+        // Same numbers, different hidden mine: identical deductions.
+        let left = layout(&["*.", "oo", "oo"]);
+        let right = layout(&[".*", "oo", "oo"]);
+        assert_eq!(deduce(&left.player_board()), deduce(&right.player_board()));
+    }
+}
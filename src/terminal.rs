@@ -0,0 +1,502 @@
+//! Terminal view layer for the host binary.
+//!
+//! The counterpart of `app`: it draws a [`Session`]'s board with ANSI escapes,
+//! turns key presses into [`Action`]s and holds no rules. It needs nothing
+//! beyond `std` and a Unix terminal, where `stty` switches raw mode on and
+//! off. Drawing and key decoding are pure functions over strings and bytes,
+//! so `cargo test` covers them like the rules.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+use crate::clock::Clock;
+use crate::config::{self, Config, ConfigError, Grid};
+use crate::game::{Action, GameResult, PlayerView};
+use crate::replay::Session;
+
+pub const USAGE: &str = "\
+usage: minesweeper [--seed N] [--<setting> VALUE]...
+
+Plays in the terminal. Every MINESWEEPER_* variable of .env.example but the
+endless board's DENSITY is also a flag, named in lower case with dashes:
+--width 30 --height 16 --mines 99, --first-click relocate, --no-guess. A flag
+given no value means true.
+
+keys: arrows or hjkl move, space or enter reveals, f flags, c chords,
+      z undoes, y redoes, n starts a new game, q quits
+
+The browser version builds with `trunk serve --open`.";
+
+/// How long a read waits for a key, in tenths of a second, before the screen
+/// is redrawn anyway so the timer moves.
+const TICK: &str = "5";
+
+/// What the command line asked for.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Args {
+    /// `seed` is `None` when the player left it to chance.
+    Play {
+        config: Config,
+        seed: Option<u64>,
+    },
+    Help,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ArgsError {
+    Unknown(String),
+    Seed(String),
+    /// The same errors, and messages, as a bad build variable.
+    Config(ConfigError),
+}
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(arg) => write!(f, "unknown argument {arg:?}"),
+            Self::Seed(value) => write!(f, "--seed must be a whole number, got {value:?}"),
+            Self::Config(error) => write!(f, "configuration error: {error}"),
+        }
+    }
+}
+
+/// Reads `--name value` and `--name=value` pairs. Each setting flag stands
+/// for the variable of the same name and goes through
+/// [`Config::from_vars`], so a bad value fails exactly as it would at build
+/// time.
+pub fn parse_args(args: &[String]) -> Result<Args, ArgsError> {
+    let mut values: HashMap<&str, String> = HashMap::new();
+    let mut seed = None;
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            return Ok(Args::Help);
+        }
+        let Some(flag) = arg.strip_prefix("--") else {
+            return Err(ArgsError::Unknown(arg.clone()));
+        };
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, value.to_owned()),
+            None => match args.next_if(|next| !next.starts_with("--")) {
+                Some(value) => (flag, value.clone()),
+                None => (flag, "true".to_owned()),
+            },
+        };
+        if name == "seed" {
+            seed = Some(value.parse().map_err(|_| ArgsError::Seed(value))?);
+            continue;
+        }
+        match config::var_named(name) {
+            Some(var) => values.insert(var, value),
+            None => return Err(ArgsError::Unknown(arg.clone())),
+        };
+    }
+    let config =
+        Config::from_vars(|var| values.get(var).map(String::as_str)).map_err(ArgsError::Config)?;
+    Ok(Args::Play { config, seed })
+}
+
+/// A key press the game understands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Reveal,
+    Flag,
+    Chord,
+    Undo,
+    Redo,
+    Restart,
+    Quit,
+}
+
+impl Key {
+    /// The move this key makes on the cell under the cursor; `None` for the
+    /// keys that only move the cursor or leave.
+    pub fn action(self, cursor: Cursor) -> Option<Action> {
+        let Cursor { row, col } = cursor;
+        match self {
+            Key::Reveal => Some(Action::Reveal(row, col)),
+            Key::Flag => Some(Action::Flag(row, col)),
+            Key::Chord => Some(Action::Chord(row, col)),
+            Key::Undo => Some(Action::Undo),
+            Key::Redo => Some(Action::Redo),
+            Key::Restart => Some(Action::Restart),
+            Key::Up | Key::Down | Key::Left | Key::Right | Key::Quit => None,
+        }
+    }
+}
+
+/// Decodes what one read from a raw terminal returned. Arrow keys arrive as
+/// `ESC [ A` to `ESC [ D`; bytes that mean nothing here are skipped.
+pub fn keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut rest = bytes;
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        let key = match byte {
+            0x1b => match rest {
+                [b'[', arrow @ b'A'..=b'D', tail @ ..] => {
+                    rest = tail;
+                    [Key::Up, Key::Down, Key::Right, Key::Left][usize::from(arrow - b'A')]
+                }
+                _ => continue,
+            },
+            b'k' => Key::Up,
+            b'j' => Key::Down,
+            b'h' => Key::Left,
+            b'l' => Key::Right,
+            b' ' | b'\r' | b'\n' => Key::Reveal,
+            b'f' => Key::Flag,
+            b'c' => Key::Chord,
+            b'z' => Key::Undo,
+            b'y' => Key::Redo,
+            b'n' => Key::Restart,
+            // Ctrl+C does not interrupt a raw terminal, so it quits here.
+            b'q' | 0x03 => Key::Quit,
+            _ => continue,
+        };
+        keys.push(key);
+    }
+    keys
+}
+
+/// The highlighted cell, in board coordinates: rows run on through every
+/// layer of a cube, so moving down off one layer lands on the next.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cursor {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Cursor {
+    /// Moves one cell, stopping at the edges of the board.
+    pub fn step(self, key: Key, config: &Config) -> Self {
+        let (rows, cols) = (config.height * config.depth, config.width);
+        let Cursor { row, col } = self;
+        match key {
+            Key::Up => Self {
+                row: row.saturating_sub(1),
+                col,
+            },
+            Key::Down => Self {
+                row: (row + 1).min(rows - 1),
+                col,
+            },
+            Key::Left => Self {
+                row,
+                col: col.saturating_sub(1),
+            },
+            Key::Right => Self {
+                row,
+                col: (col + 1).min(cols - 1),
+            },
+            _ => self,
+        }
+    }
+}
+
+/// One full frame: the counters, the board and a key reminder. `now` is read
+/// from the clock the session runs on. Lines end in `\r\n`, as a raw
+/// terminal needs, and each clears what the last frame left after it.
+pub fn render(session: &Session, cursor: Cursor, now: u64) -> String {
+    let state = session.history().present();
+    let board = &state.board;
+    let config = board.config();
+    let mut frame = String::from("\x1b[H");
+    let mut line = |text: &str| {
+        frame.push_str(text);
+        frame.push_str("\x1b[K\r\n");
+    };
+    let seconds = session.stopwatch().elapsed(now) / 1_000;
+    let counter = match board.lives_remaining() {
+        _ if config.lives == 1 => format!("{} mines left", board.mines_remaining()),
+        1 => format!("{} mines left, last life", board.mines_remaining()),
+        lives => format!("{} mines left, {lives} lives left", board.mines_remaining()),
+    };
+    let result = match (state.result, session.failure()) {
+        (_, Some(error)) => format!("  \x1b[1;31m{error}\x1b[0m"),
+        (Some(GameResult::Won), None) => String::from("  \x1b[1;32mSwept.\x1b[0m"),
+        (Some(GameResult::Lost), None) => String::from("  \x1b[1;31mBoom.\x1b[0m"),
+        (None, None) => String::new(),
+    };
+    line(&format!("Minesweeper  {counter}  {seconds:03}{result}"));
+    line("");
+    for (row, views) in board.view_rows().enumerate() {
+        if row > 0 && row % config.height == 0 {
+            line("");
+        }
+        let mut text = match config.grid == Grid::Hex && row % config.height % 2 == 1 {
+            true => String::from(" "),
+            false => String::new(),
+        };
+        for (col, view) in views.into_iter().enumerate() {
+            let (style, glyph) = face(view);
+            let highlight = match cursor == (Cursor { row, col }) {
+                true => "\x1b[7m",
+                false => "",
+            };
+            text.push_str(&format!("{style}{highlight}{glyph:<2}\x1b[0m"));
+        }
+        line(&text);
+    }
+    line("");
+    line("arrows/hjkl move  space reveal  f flag  c chord  z undo  y redo  n new  q quit");
+    frame.push_str("\x1b[J");
+    frame
+}
+
+/// Colour and text of a cell. Numbers take the desktop game's colours, and
+/// stacks show their count as they do in the browser.
+fn face(view: PlayerView) -> (&'static str, String) {
+    const NUMBERS: [&str; 8] = [
+        "\x1b[34m", "\x1b[32m", "\x1b[31m", "\x1b[35m", "\x1b[33m", "\x1b[36m", "\x1b[37m",
+        "\x1b[90m",
+    ];
+    match view {
+        PlayerView::Gap => ("", String::from(" ")),
+        PlayerView::Flagged(1) => ("\x1b[1;31m", String::from("F")),
+        PlayerView::Flagged(count) => ("\x1b[1;31m", format!("F{count}")),
+        PlayerView::Questioned => ("\x1b[1m", String::from("?")),
+        PlayerView::Covered => ("\x1b[90m", String::from(".")),
+        PlayerView::Mine(1) => ("\x1b[1;41m", String::from("*")),
+        PlayerView::Mine(count) => ("\x1b[1;41m", format!("*{count}")),
+        PlayerView::Number(0) => ("", String::from(" ")),
+        PlayerView::Number(count) => (
+            NUMBERS[usize::from(count - 1).min(NUMBERS.len() - 1)],
+            count.to_string(),
+        ),
+    }
+}
+
+/// Milliseconds since the game was launched; `Instant` never runs backwards.
+struct SystemClock(Instant);
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        self.0.elapsed().as_millis() as u64
+    }
+}
+
+/// Raw mode and the alternate screen for as long as it lives; dropping it
+/// puts the terminal back the way the shell had it.
+struct Screen {
+    saved: String,
+}
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(&["raw", "-echo", "min", "0", "time", TICK])?;
+        let mut stdout = io::stdout();
+        stdout.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J")?;
+        stdout.flush()?;
+        Ok(Self { saved })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(b"\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+        let _ = stty(&[self.saved.trim()]);
+    }
+}
+
+/// `stty` acts on the terminal it is given as its stdin, which is ours.
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(
+            "stdin is not a terminal that `stty` can put in raw mode",
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Plays until the player quits. A read that times out with no key still
+/// redraws, so the timer keeps moving.
+pub fn run(config: Config, seed: u64) -> io::Result<()> {
+    let _screen = Screen::enter()?;
+    let clock = SystemClock(Instant::now());
+    let mut session = Session::new(config, seed, &clock);
+    let mut cursor = Cursor::default();
+    let (mut stdin, mut stdout) = (io::stdin(), io::stdout());
+    let mut buffer = [0; 64];
+    loop {
+        stdout.write_all(render(&session, cursor, clock.now()).as_bytes())?;
+        stdout.flush()?;
+        let read = stdin.read(&mut buffer)?;
+        for key in keys(&buffer[..read]) {
+            if key == Key::Quit {
+                return Ok(());
+            }
+            let config = session.history().present().board.config();
+            cursor = cursor.step(key, config);
+            if let Some(action) = key.action(cursor) {
+                session = session.apply(action, &clock);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::config::{FirstClick, Generation};
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_owned).collect()
+    }
+
+    /// The frame without its colours and line clearing.
+    fn plain(frame: &str) -> Vec<String> {
+        let mut text = String::new();
+        let mut chars = frame.chars();
+        while let Some(glyph) = chars.next() {
+            match glyph {
+                '\x1b' => {
+                    chars.find(|glyph| glyph.is_ascii_alphabetic());
+                }
+                '\r' => {}
+                glyph => text.push(glyph),
+            }
+        }
+        text.lines()
+            .map(|line| line.trim_end().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn flags_build_the_config_their_variables_would() {
+        let parsed = parse_args(&args(
+            "--width 9 --height=9 --mines 10 --no-guess --first-click safe-cell --seed 4",
+        ));
+        let config = Config::new(9, 9, 10)
+            .expect("valid config")
+            .with_generation(Generation::NoGuess)
+            .with_first_click(FirstClick::SafeCell)
+            .check()
+            .expect("valid config");
+        assert_eq!(
+            parsed,
+            Ok(Args::Play {
+                config,
+                seed: Some(4)
+            })
+        );
+        assert_eq!(
+            parse_args(&[]),
+            Ok(Args::Play {
+                config: Config::from_vars(|_| None).expect("defaults are valid"),
+                seed: None
+            })
+        );
+        assert_eq!(parse_args(&args("--width 3 --help")), Ok(Args::Help));
+    }
+
+    #[test]
+    fn a_bad_flag_fails_with_the_build_variable_message() {
+        let error = parse_args(&args("--width 0")).expect_err("zero width");
+        assert_eq!(
+            error,
+            ArgsError::Config(ConfigError::ZeroDimension {
+                var: "MINESWEEPER_WIDTH"
+            })
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "configuration error: {}",
+                Config::new(0, 9, 1).expect_err("zero width")
+            )
+        );
+        assert_eq!(
+            parse_args(&args("--colour red")),
+            Err(ArgsError::Unknown("--colour".to_owned()))
+        );
+        assert_eq!(
+            parse_args(&args("9")),
+            Err(ArgsError::Unknown("9".to_owned()))
+        );
+        assert_eq!(
+            parse_args(&args("--seed x")),
+            Err(ArgsError::Seed("x".to_owned()))
+        );
+    }
+
+    #[test]
+    fn key_presses_decode_arrows_and_letters_alike() {
+        assert_eq!(
+            keys(b"\x1b[A\x1b[Bhl f\rcq"),
+            [
+                Key::Up,
+                Key::Down,
+                Key::Left,
+                Key::Right,
+                Key::Reveal,
+                Key::Flag,
+                Key::Reveal,
+                Key::Chord,
+                Key::Quit
+            ]
+        );
+        // A lone escape, an unknown sequence and stray letters are dropped.
+        assert_eq!(keys(b"\x1bx\x1b[Hqz"), [Key::Quit, Key::Undo]);
+        assert_eq!(
+            Key::Flag.action(Cursor { row: 2, col: 3 }),
+            Some(Action::Flag(2, 3))
+        );
+        assert_eq!(Key::Left.action(Cursor::default()), None);
+    }
+
+    #[test]
+    fn the_cursor_stops_at_the_edges_and_runs_on_through_the_layers() {
+        let cube = Config::new(3, 2, 0)
+            .expect("valid config")
+            .with_depth(2)
+            .check()
+            .expect("valid config");
+        let corner = Cursor { row: 0, col: 0 };
+        assert_eq!(corner.step(Key::Up, &cube), corner);
+        assert_eq!(corner.step(Key::Left, &cube), corner);
+        let bottom = (0..9).fold(corner, |cursor, _| cursor.step(Key::Down, &cube));
+        assert_eq!(bottom, Cursor { row: 3, col: 0 });
+        let right = (0..9).fold(bottom, |cursor, _| cursor.step(Key::Right, &cube));
+        assert_eq!(right, Cursor { row: 3, col: 2 });
+    }
+
+    #[test]
+    fn a_frame_shows_the_counter_the_board_and_the_cursor() {
+        let clock = ManualClock::new(0);
+        let config = Config::new(6, 4, 3).expect("valid config");
+        let session = Session::new(config, 5, &clock)
+            .apply(Action::Reveal(0, 0), &clock)
+            .apply(Action::Flag(3, 5), &clock);
+        clock.advance(12_500);
+        let frame = render(&session, Cursor { row: 1, col: 2 }, clock.now());
+        assert!(frame.contains("\x1b[7m"), "the cursor is highlighted");
+        assert_eq!(plain(&frame), GOLDEN_FRAME);
+    }
+
+    const GOLDEN_FRAME: [&str; 8] = [
+        "Minesweeper  2 mines left  012",
+        "",
+        "    1 . . .",
+        "1 1 1 . . .",
+        ". . . . . .",
+        ". . . . . F",
+        "",
+        "arrows/hjkl move  space reveal  f flag  c chord  z undo  y redo  n new  q quit",
+    ];
+}
@@ -0,0 +1,125 @@
+//! Drives the `engine` binary the way a bot in another language would: over
+//! its stdin and stdout, knowing only the protocol.
+
+use std::io::{BufRead, BufReader, Lines, Write};
+use std::process::{ChildStdin, ChildStdout, Command, Stdio};
+
+struct Bot {
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl Bot {
+    fn send(&mut self, line: &str) -> String {
+        writeln!(self.stdin, "{line}").expect("the engine reads its stdin");
+        self.read()
+    }
+
+    fn read(&mut self) -> String {
+        self.stdout
+            .next()
+            .expect("the engine answers")
+            .expect("the answer is text")
+    }
+
+    /// The `board` answer as tokens, after checking its header.
+    fn board(&mut self) -> Vec<Vec<String>> {
+        let header = self.send("board");
+        let fields: Vec<&str> = header.split(' ').collect();
+        assert_eq!(fields[..3], ["board", "9", "9"], "{header}");
+        (0..9)
+            .map(|_| self.read().split(' ').map(str::to_owned).collect())
+            .collect()
+    }
+}
+
+fn neighbours(row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> {
+    (row.saturating_sub(1)..=(row + 1).min(8))
+        .flat_map(move |r| (col.saturating_sub(1)..=(col + 1).min(8)).map(move |c| (r, c)))
+        .filter(move |&cell| cell != (row, col))
+}
+
+/// The next move by the two textbook rules: a number with as many covered
+/// neighbours as it has mines left flags them all, and a number whose flags
+/// are all placed has the rest of its neighbours safe. Failing both, it
+/// guesses the first covered cell.
+fn next_move(board: &[Vec<String>]) -> String {
+    let covered = |cells: &[(usize, usize)], token: &str| -> Vec<(usize, usize)> {
+        cells
+            .iter()
+            .copied()
+            .filter(|&(row, col)| board[row][col] == token)
+            .collect()
+    };
+    for (row, line) in board.iter().enumerate() {
+        for (col, token) in line.iter().enumerate() {
+            let Ok(count) = token.parse::<usize>() else {
+                continue;
+            };
+            let around: Vec<_> = neighbours(row, col).collect();
+            let (hidden, flags) = (covered(&around, "-"), covered(&around, "F"));
+            if hidden.is_empty() {
+                continue;
+            }
+            if flags.len() == count {
+                let (row, col) = hidden[0];
+                return format!("reveal {row} {col}");
+            }
+            if flags.len() + hidden.len() == count {
+                let (row, col) = hidden[0];
+                return format!("flag {row} {col}");
+            }
+        }
+    }
+    let (row, line) = board
+        .iter()
+        .enumerate()
+        .find(|(_, line)| line.iter().any(|token| token == "-"))
+        .expect("a game in play has a covered cell");
+    let col = line.iter().position(|token| token == "-").expect("found");
+    format!("reveal {row} {col}")
+}
+
+#[test]
+fn a_scripted_bot_plays_a_whole_game_over_the_protocol() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_engine"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("the engine starts");
+    let mut bot = Bot {
+        stdin: child.stdin.take().expect("piped"),
+        stdout: BufReader::new(child.stdout.take().expect("piped")).lines(),
+    };
+
+    assert_eq!(bot.send("board"), "error no game; start one with new");
+    assert_eq!(
+        bot.send("new 11 width=9 height=9 mines=10 no-guess=true"),
+        "ok 9 9 10"
+    );
+    let mut answer = bot.send("reveal 4 4");
+    let mut moves = 1;
+    while answer == "ok playing" {
+        let board = bot.board();
+        assert!(
+            board.iter().flatten().all(|token| token != "*"),
+            "a mine is shown before the game is over"
+        );
+        answer = bot.send(&next_move(&board));
+        moves += 1;
+        assert!(moves < 200, "the game should have ended by now");
+    }
+    // On this no-guess board the textbook rules never have to guess wrong.
+    assert_eq!(answer, "ok won");
+    assert_eq!(bot.send("result"), "result won");
+    let still_covered = bot
+        .board()
+        .into_iter()
+        .flatten()
+        .filter(|token| token == "-" || token == "F")
+        .count();
+    assert_eq!(still_covered, 10, "only the mines are left covered");
+
+    writeln!(bot.stdin, "quit").expect("the engine reads its stdin");
+    assert!(child.wait().expect("the engine exits").success());
+}